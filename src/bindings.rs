@@ -0,0 +1,275 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use ruscii::keyboard::{Key, KeyEvent};
+
+use crate::keys::{key_from_name, key_name};
+use crate::paths::{self, invalid_data};
+
+// What a key does in a game. Each game binds the actions it needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Player2Up,
+    Player2Down,
+    Fire,
+    Pause,
+}
+
+// Every action with its name in files (config, replays) and on screen.
+const ACTIONS: [(Action, &str, &str); 8] = [
+    (Action::MoveLeft, "move_left", "move left"),
+    (Action::MoveRight, "move_right", "move right"),
+    (Action::MoveUp, "move_up", "move up"),
+    (Action::MoveDown, "move_down", "move down"),
+    (Action::Player2Up, "player2_up", "player 2 up"),
+    (Action::Player2Down, "player2_down", "player 2 down"),
+    (Action::Fire, "fire", "fire"),
+    (Action::Pause, "pause", "pause"),
+];
+
+// Every game can be paused with these keys unless they are bound otherwise.
+const PAUSE_KEYS: [Key; 2] = [Key::Esc, Key::P];
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        ACTIONS.iter().find(|(action, _, _)| action == self).map(|(_, name, _)| *name).unwrap()
+    }
+
+    pub fn description(&self) -> &'static str {
+        ACTIONS.iter().find(|(action, _, _)| action == self).map(|(_, _, text)| *text).unwrap()
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|(_, action_name, _)| *action_name == name).map(|(action, _, _)| *action)
+    }
+}
+
+// An action started or stopped, as a key is pressed or released.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionEvent {
+    Pressed(Action),
+    Released(Action),
+}
+
+/*
+    Bindings maps the keys of the keyboard to the actions of one game.
+    - the name of the game
+    - the keys of every action by default
+    - the keys of every action, as chosen by the player
+
+    The choices of the player are kept in `bindings.toml` in the config
+    directory, with one table per game and one list of key names per action:
+
+        [space-invaders]
+        move_left = ["A", "Left"]
+        fire = ["Space", "K"]
+*/
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    pub game: String,
+    defaults: Vec<(Action, Vec<Key>)>,
+    keys: Vec<(Action, Vec<Key>)>,
+}
+
+fn path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("bindings.toml"))
+}
+
+// Reads the whole config file. A missing file is an empty config.
+fn read_config() -> io::Result<toml::Table> {
+    let path = path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    match fs::read_to_string(path) {
+        Ok(text) => text.parse().map_err(|error: toml::de::Error| invalid_data(error.to_string())),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(error) => Err(error),
+    }
+}
+
+impl Bindings {
+    // The default bindings of a game, plus the pause keys.
+    pub fn new(game: &str, defaults: &[(Action, &[Key])]) -> Bindings {
+        let mut defaults: Vec<(Action, Vec<Key>)> =
+            defaults.iter().map(|(action, keys)| (*action, keys.to_vec())).collect();
+        if !defaults.iter().any(|(action, _)| *action == Action::Pause) {
+            defaults.push((Action::Pause, PAUSE_KEYS.to_vec()));
+        }
+        Bindings {
+            game: game.to_string(),
+            keys: defaults.clone(),
+            defaults,
+        }
+    }
+
+    // The bindings of a game, as chosen by the player in the config file.
+    // Anything missing or wrong in the file is left as the default.
+    pub fn load(game: &str, defaults: &[(Action, &[Key])]) -> Bindings {
+        let mut bindings = Bindings::new(game, defaults);
+        let config = match read_config() {
+            Ok(config) => config,
+            Err(error) => {
+                log::warn!("could not read the key bindings: {}", error);
+                return bindings;
+            }
+        };
+
+        let table = match config.get(&paths::slug(game)).and_then(toml::Value::as_table) {
+            Some(table) => table,
+            None => return bindings,
+        };
+        for (name, value) in table {
+            let action = match Action::from_name(name) {
+                Some(action) if bindings.defaults.iter().any(|(bound, _)| *bound == action) => action,
+                _ => {
+                    log::warn!("{} has no action named {}", game, name);
+                    continue;
+                }
+            };
+            let names = value.as_array().map(Vec::as_slice).unwrap_or_default();
+            let keys: Vec<Key> = names
+                .iter()
+                .filter_map(|name| {
+                    let key = name.as_str().and_then(key_from_name);
+                    if key.is_none() {
+                        log::warn!("unknown key {} for {} in {}", name, action.name(), game);
+                    }
+                    key
+                })
+                .collect();
+            if !bindings.set(action, keys) {
+                log::warn!("ignoring the keys of {} in {}: no key would pause the game", action.name(), game);
+            }
+        }
+        bindings
+    }
+
+    // Writes the bindings of the game to the config file, keeping those of
+    // the other games.
+    pub fn save(&self) -> io::Result<()> {
+        let path = path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        let mut config = read_config()?;
+
+        let mut table = toml::Table::new();
+        for (action, keys) in &self.keys {
+            let names = keys
+                .iter()
+                .filter_map(|key| key_name(*key))
+                .map(|name| toml::Value::String(name.to_string()))
+                .collect();
+            table.insert(action.name().to_string(), toml::Value::Array(names));
+        }
+        config.insert(paths::slug(&self.game), toml::Value::Table(table));
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, config.to_string())
+    }
+
+    // Every action of the game with the keys it is bound to.
+    pub fn entries(&self) -> &[(Action, Vec<Key>)] {
+        &self.keys
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.keys
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.keys.iter().find(|(_, keys)| keys.contains(&key)).map(|(action, _)| *action)
+    }
+
+    // The action started or stopped by a key event, if the key is bound.
+    pub fn event(&self, key_event: KeyEvent) -> Option<ActionEvent> {
+        match key_event {
+            KeyEvent::Pressed(key) => self.action(key).map(ActionEvent::Pressed),
+            KeyEvent::Released(key) => self.action(key).map(ActionEvent::Released),
+        }
+    }
+
+    // The actions of the keys that are held down, each only once.
+    pub fn actions_down(&self, keys_down: &[Key]) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for action in keys_down.iter().filter_map(|key| self.action(*key)) {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+
+    // Binds an action to the given keys only. The keys are taken away from
+    // any other action they were bound to. Returns false, changing nothing,
+    // if no key would be left to pause the game: it is the only way out of it.
+    pub fn set(&mut self, action: Action, keys: Vec<Key>) -> bool {
+        let can_pause = if action == Action::Pause {
+            !keys.is_empty()
+        } else {
+            self.keys(Action::Pause).iter().any(|key| !keys.contains(key))
+        };
+        if !can_pause {
+            return false;
+        }
+
+        for (bound, bound_keys) in self.keys.iter_mut() {
+            if *bound != action {
+                bound_keys.retain(|key| !keys.contains(key));
+            }
+        }
+        if let Some((_, bound_keys)) = self.keys.iter_mut().find(|(bound, _)| *bound == action) {
+            *bound_keys = keys;
+        }
+        true
+    }
+
+    // Binds an action back to its default keys.
+    pub fn reset(&mut self, action: Action) {
+        let defaults = self.defaults.iter().find(|(bound, _)| *bound == action);
+        if let Some(keys) = defaults.map(|(_, keys)| keys.clone()) {
+            self.set(action, keys);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings() -> Bindings {
+        Bindings::new("Test", &[(Action::Fire, &[Key::Space]), (Action::MoveLeft, &[Key::A])])
+    }
+
+    #[test]
+    fn set_takes_keys_from_other_actions() {
+        let mut bindings = bindings();
+        assert!(bindings.set(Action::MoveLeft, vec![Key::Space, Key::P]));
+        assert_eq!(bindings.keys(Action::MoveLeft), [Key::Space, Key::P]);
+        assert_eq!(bindings.keys(Action::Fire), []);
+        assert_eq!(bindings.keys(Action::Pause), [Key::Esc]);
+
+        bindings.reset(Action::Fire);
+        assert_eq!(bindings.keys(Action::Fire), [Key::Space]);
+        assert_eq!(bindings.keys(Action::MoveLeft), [Key::P]);
+    }
+
+    #[test]
+    fn set_keeps_a_pause_key() {
+        let mut bindings = bindings();
+        assert!(!bindings.set(Action::Pause, vec![]));
+        assert!(!bindings.set(Action::Fire, vec![Key::Esc, Key::P]));
+        assert_eq!(bindings.keys(Action::Pause), PAUSE_KEYS);
+        assert_eq!(bindings.keys(Action::Fire), [Key::Space]);
+
+        assert!(bindings.set(Action::Pause, vec![Key::Q]));
+        assert!(bindings.set(Action::Fire, vec![Key::Esc, Key::P]));
+        assert_eq!(bindings.keys(Action::Pause), [Key::Q]);
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+// The most time a single frame can catch up on, so that the game does not
+// fast-forward after the terminal was stalled (or the process suspended).
+const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+/*
+    Clock turns the real time between frames into fixed-length simulation
+    ticks, so that a game runs at the same speed whatever the frame rate.
+    - how long a tick lasts
+    - the time that passed but was not turned into ticks yet
+    - when the time was last looked at
+*/
+pub struct Clock {
+    tick: Duration,
+    accumulated: Duration,
+    last: Instant,
+}
+
+impl Clock {
+    pub fn new(ticks_per_second: u32) -> Clock {
+        Clock {
+            tick: Duration::from_secs(1) / ticks_per_second.max(1),
+            accumulated: Duration::ZERO,
+            last: Instant::now(),
+        }
+    }
+
+    // The number of ticks to run for the time that passed since the last call.
+    pub fn ticks(&mut self) -> u32 {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        self.advance(elapsed)
+    }
+
+    // The number of ticks to run once `elapsed` more time has passed.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulated = (self.accumulated + elapsed).min(MAX_CATCH_UP);
+        let mut ticks = 0;
+        while self.accumulated >= self.tick {
+            self.accumulated -= self.tick;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    // Forgets the time that passed since the last call (e.g. while paused).
+    pub fn reset(&mut self) {
+        self.accumulated = Duration::ZERO;
+        self.last = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_runs_whole_ticks() {
+        let mut clock = Clock::new(10);
+        assert_eq!(clock.advance(Duration::from_millis(50)), 0);
+        assert_eq!(clock.advance(Duration::from_millis(50)), 1);
+        assert_eq!(clock.advance(Duration::from_millis(250)), 2);
+        // The 50ms left over count towards the next tick
+        assert_eq!(clock.advance(Duration::from_millis(50)), 1);
+    }
+
+    #[test]
+    fn advance_caps_the_catch_up() {
+        let mut clock = Clock::new(100);
+        assert_eq!(clock.advance(Duration::from_secs(10)), 25);
+        assert_eq!(clock.advance(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn reset_forgets_the_time_left_over() {
+        let mut clock = Clock::new(10);
+        assert_eq!(clock.advance(Duration::from_millis(90)), 0);
+        clock.reset();
+        assert_eq!(clock.advance(Duration::from_millis(90)), 0);
+    }
+}
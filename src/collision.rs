@@ -0,0 +1,196 @@
+use ruscii::spatial::Vec2;
+
+use crate::physics::Vec2f;
+
+/*
+    Rect is an axis-aligned box, in cell units.
+    - the position of its top left corner
+    - its width and height
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub position: Vec2f,
+    pub dimension: Vec2f,
+}
+
+/*
+    Side is the side of a box that was hit. The sides follow the screen:
+    Top is the side with the smallest y.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Side {
+    // The unit vector pointing out of the box from this side.
+    pub fn normal(&self) -> Vec2f {
+        match self {
+            Side::Left => Vec2f::xy(-1.0, 0.0),
+            Side::Right => Vec2f::xy(1.0, 0.0),
+            Side::Top => Vec2f::xy(0.0, -1.0),
+            Side::Bottom => Vec2f::xy(0.0, 1.0),
+        }
+    }
+
+    // Whether it is the left or the right side.
+    pub fn is_vertical(&self) -> bool {
+        matches!(self, Side::Left | Side::Right)
+    }
+}
+
+/*
+    Hit describes where a moving point first touched a box.
+    - the fraction of the motion at which it happened (from 0 to 1)
+    - the side of the box it went through
+    - the position of the point at that time
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub time: f32,
+    pub side: Side,
+    pub position: Vec2f,
+}
+
+impl Rect {
+    pub fn new(position: Vec2f, dimension: Vec2f) -> Rect {
+        Rect { position, dimension }
+    }
+
+    // The box covering `dimension` cells from the cell at `position`. A point
+    // is inside it when it is drawn in one of those cells.
+    pub fn from_cells(position: Vec2, dimension: Vec2) -> Rect {
+        Rect::new(Vec2f::from(position) - Vec2f::xy(0.5, 0.5), Vec2f::from(dimension))
+    }
+
+    pub fn end(&self) -> Vec2f {
+        self.position + self.dimension
+    }
+
+    pub fn contains(&self, point: Vec2f) -> bool {
+        let end = self.end();
+        point.x >= self.position.x && point.x < end.x && point.y >= self.position.y && point.y < end.y
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        let end = self.end();
+        let other_end = other.end();
+        self.position.x < other_end.x
+            && other.position.x < end.x
+            && self.position.y < other_end.y
+            && other.position.y < end.y
+    }
+}
+
+// The fractions of the motion at which `start` enters and leaves the slab
+// between `low` and `high` on one axis.
+fn slab(start: f32, motion: f32, low: f32, high: f32) -> (f32, f32) {
+    if motion > 0.0 {
+        ((low - start) / motion, (high - start) / motion)
+    } else if motion < 0.0 {
+        ((high - start) / motion, (low - start) / motion)
+    } else if start >= low && start < high {
+        (f32::NEG_INFINITY, f32::INFINITY)
+    } else {
+        (f32::INFINITY, f32::NEG_INFINITY)
+    }
+}
+
+// Moves a point from `start` by `motion` and returns where it first enters
+// the box, if it does. A point that starts inside the box does not hit it.
+pub fn sweep_point(start: Vec2f, motion: Vec2f, rect: &Rect) -> Option<Hit> {
+    let end = rect.end();
+    let (x_entry, x_exit) = slab(start.x, motion.x, rect.position.x, end.x);
+    let (y_entry, y_exit) = slab(start.y, motion.y, rect.position.y, end.y);
+
+    let entry = x_entry.max(y_entry);
+    let exit = x_exit.min(y_exit);
+    if entry > exit || exit <= 0.0 || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+
+    let side = if x_entry > y_entry {
+        if motion.x > 0.0 { Side::Left } else { Side::Right }
+    } else if motion.y > 0.0 {
+        Side::Top
+    } else {
+        Side::Bottom
+    };
+
+    Some(Hit {
+        time: entry,
+        side,
+        position: start + motion * entry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect() -> Rect {
+        Rect::new(Vec2f::xy(2.0, 2.0), Vec2f::xy(2.0, 2.0))
+    }
+
+    fn hit(start: (f32, f32), motion: (f32, f32)) -> Option<Hit> {
+        sweep_point(Vec2f::xy(start.0, start.1), Vec2f::xy(motion.0, motion.1), &rect())
+    }
+
+    #[test]
+    fn sweep_point_sides() {
+        let sides = [
+            ((0.0, 3.0), (4.0, 0.0), Side::Left, (2.0, 3.0)),
+            ((6.0, 3.0), (-4.0, 0.0), Side::Right, (4.0, 3.0)),
+            ((3.0, 0.0), (0.0, 4.0), Side::Top, (3.0, 2.0)),
+            ((3.0, 6.0), (0.0, -4.0), Side::Bottom, (3.0, 4.0)),
+        ];
+        for (start, motion, side, position) in sides {
+            let hit = hit(start, motion).unwrap();
+            assert_eq!(hit.time, 0.5);
+            assert_eq!(hit.side, side);
+            assert_eq!(hit.position, Vec2f::xy(position.0, position.1));
+        }
+    }
+
+    #[test]
+    fn sweep_point_time_of_impact() {
+        let hit = hit((2.5, 0.0), (1.0, 8.0)).unwrap();
+        assert_eq!(hit.time, 0.25);
+        assert_eq!(hit.side, Side::Top);
+        assert_eq!(hit.position, Vec2f::xy(2.75, 2.0));
+
+        // Going in diagonally, the point comes in through the side it
+        // reaches last
+        let hit = self::hit((1.0, 0.0), (2.0, 6.0)).unwrap();
+        assert_eq!(hit.time, 0.5);
+        assert_eq!(hit.side, Side::Left);
+        assert_eq!(hit.position, Vec2f::xy(2.0, 3.0));
+    }
+
+    #[test]
+    fn sweep_point_misses() {
+        // Going past the box
+        assert_eq!(hit((0.0, 0.0), (6.0, 0.0)), None);
+        // Stopping short of it
+        assert_eq!(hit((0.0, 3.0), (1.0, 0.0)), None);
+        // Moving away from it
+        assert_eq!(hit((0.0, 3.0), (-4.0, 0.0)), None);
+        // Starting inside it
+        assert_eq!(hit((3.0, 3.0), (4.0, 0.0)), None);
+        // Not moving
+        assert_eq!(hit((0.0, 3.0), (0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn cells() {
+        let rect = Rect::from_cells(Vec2::xy(1, 1), Vec2::xy(2, 1));
+        assert!(rect.contains(Vec2f::xy(1.0, 1.0)));
+        assert!(rect.contains(Vec2f::xy(2.4, 1.4)));
+        assert!(!rect.contains(Vec2f::xy(2.5, 1.0)));
+        assert!(rect.intersects(&Rect::from_cells(Vec2::xy(2, 1), Vec2::xy(1, 1))));
+        assert!(!rect.intersects(&Rect::from_cells(Vec2::xy(3, 1), Vec2::xy(1, 1))));
+    }
+}
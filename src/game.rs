@@ -0,0 +1,446 @@
+use std::fs;
+use std::io;
+
+use ruscii::app::{App, State};
+use ruscii::drawing::{Pencil, RectCharset};
+use ruscii::gui::FPSCounter;
+use ruscii::keyboard::{Key, KeyEvent, Keyboard};
+use ruscii::spatial::Vec2;
+use ruscii::terminal::{Canvas, Color, Window};
+
+use crate::bindings::{Action, ActionEvent, Bindings};
+use crate::clock::Clock;
+use crate::highscores::{self, HighScore, HighScores, INITIALS_LEN};
+use crate::keys::key_letter;
+use crate::options::Options;
+use crate::pause::{PauseAction, PauseMenu, Settings};
+use crate::replay::Replay;
+
+// The keys that can be pressed once a game is over and its scores are shown
+const GAME_OVER_HELP: &str = "enter: continue  -  r: play again";
+
+/*
+    Input is what the player did during one tick, in terms of the actions
+    of the game (so that it does not depend on the key bindings).
+    - the actions started or stopped since the last tick
+    - the actions whose keys are held down
+    - the new size of the window, if it was resized since the last tick
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Input {
+    pub events: Vec<ActionEvent>,
+    pub actions_down: Vec<Action>,
+    pub win_size: Option<Vec2>,
+}
+
+impl Input {
+    // Adds what happened on the keyboard during the last frame.
+    pub fn read_keyboard(&mut self, keyboard: &Keyboard, bindings: &Bindings) {
+        for key_event in keyboard.last_key_events().iter().copied() {
+            self.events.extend(bindings.event(key_event));
+        }
+        self.actions_down = bindings.actions_down(&keyboard.get_keys_down());
+    }
+
+    // Takes the input for the next tick. Action events are only given once.
+    pub fn take_tick(&mut self) -> Input {
+        Input {
+            events: std::mem::take(&mut self.events),
+            actions_down: self.actions_down.clone(),
+            win_size: self.win_size.take(),
+        }
+    }
+}
+
+/*
+    Game is implemented by every title in `src/games` so that they can all be
+    driven the same way (by the launcher, replays, high scores and tests).
+    A game only owns its state: reading the keyboard, timing and drawing to
+    the terminal are left to whoever drives it.
+*/
+pub trait Game {
+    // The name of the game, as shown in the launcher and saved in replays.
+    const NAME: &'static str;
+
+    // How many times per second `update` is called, whatever the frame rate.
+    const TICKS_PER_SECOND: u32;
+
+    // The smallest window the game can be played in. The game is paused with
+    // a message while the window is smaller than that.
+    const MIN_WINDOW: Vec2;
+
+    // The actions of the game and the keys they are bound to by default.
+    // Every game can also be paused, so Pause does not need to be listed.
+    const BINDINGS: &'static [(Action, &'static [Key])];
+
+    // Whether a border is drawn around the playfield when the window has
+    // room for it. Games that draw their own border turn it off.
+    const BORDER: bool = true;
+
+    // Creates a new game that has `dimension` cells of space to play in.
+    // All of its randomness must come from an RNG seeded with `seed`.
+    fn new(dimension: Vec2, seed: u64) -> Self
+    where
+        Self: Sized;
+
+    // The size of the playfield. It is centered in the window when rendered.
+    fn dimension(&self) -> Vec2;
+
+    // Plays the level given as text (see `--level`) instead of the game's
+    // own, right after the game is created. Games without levels ignore it.
+    fn load_level(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Called when the window was resized, before the input of the tick.
+    // Games that fit their playfield to the window lay it out again here;
+    // the others are left as they are and centered in the new window.
+    fn resize(&mut self, _win_size: Vec2) {}
+
+    // Called once for every action started or stopped since the last tick.
+    fn handle_action_event(&mut self, _event: &ActionEvent) {}
+
+    // Called once per tick with every action whose keys are held down.
+    fn handle_actions_down(&mut self, _actions: &[Action]) {}
+
+    // Advances the game by one tick.
+    fn update(&mut self);
+
+    // Draws the game. The pencil origin is the top left of the playfield.
+    fn render(&self, pencil: &mut Pencil);
+
+    fn score(&self) -> usize;
+
+    fn is_finished(&self) -> bool;
+
+    // The variant of the game being played, saved with high scores.
+    fn mode(&self) -> String {
+        "classic".to_string()
+    }
+
+    // What the player should read once the game is over, such as who won.
+    // It is shown at the top of the game over panel, which hides the
+    // playfield.
+    fn summary(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/*
+    GameOver defines what is shown on top of a finished game.
+    - the prompt for the initials of a new high score
+    - the high-score table, with the rank of the new score (if any)
+*/
+enum GameOver {
+    EnteringInitials(HighScore),
+    ShowingScores(Option<usize>),
+}
+
+impl GameOver {
+    // Handles a key pressed once the game is over. Returns what to show next,
+    // or None when the player is done with the game.
+    fn handle_key_event(self, key_event: KeyEvent, high_scores: &mut Option<HighScores>) -> Option<GameOver> {
+        match self {
+            GameOver::EnteringInitials(mut high_score) => match key_event {
+                KeyEvent::Pressed(Key::Enter) if !high_score.initials.is_empty() => {
+                    let table = high_scores.as_mut()?;
+                    let rank = table.insert(high_score);
+                    if let Err(error) = table.save() {
+                        log::warn!("could not save the high scores of {}: {}", table.game, error);
+                    }
+                    Some(GameOver::ShowingScores(rank))
+                }
+                KeyEvent::Pressed(Key::Esc) => Some(GameOver::ShowingScores(None)),
+                KeyEvent::Pressed(Key::Backspace) => {
+                    high_score.initials.pop();
+                    Some(GameOver::EnteringInitials(high_score))
+                }
+                KeyEvent::Pressed(key) => {
+                    if let Some(letter) = key_letter(key) {
+                        if high_score.initials.len() < INITIALS_LEN {
+                            high_score.initials.push(letter);
+                        }
+                    }
+                    Some(GameOver::EnteringInitials(high_score))
+                }
+                _ => Some(GameOver::EnteringInitials(high_score)),
+            },
+            GameOver::ShowingScores(rank) => match key_event {
+                KeyEvent::Pressed(Key::Enter | Key::Esc | Key::Q) => None,
+                _ => Some(GameOver::ShowingScores(rank)),
+            },
+        }
+    }
+
+    // Draws the panel, with the summary of the game at its top.
+    fn draw(
+        &self,
+        pencil: &mut Pencil,
+        high_scores: &Option<HighScores>,
+        summary: &[String],
+        center: Vec2,
+    ) {
+        match (self, high_scores) {
+            (GameOver::EnteringInitials(high_score), _) => {
+                let HighScore { score, initials, .. } = high_score;
+                highscores::draw_initials_prompt(pencil, summary, *score, initials, center)
+            }
+            (GameOver::ShowingScores(rank), Some(table)) => {
+                highscores::draw_table(pencil, summary, table, *rank, GAME_OVER_HELP, center)
+            }
+            // Without a table (as in replays) there are still keys to press
+            (GameOver::ShowingScores(_), None) => {
+                let mut lines = highscores::summary_lines(summary);
+                lines.extend(["game over".to_string(), String::new(), GAME_OVER_HELP.to_string()]);
+                highscores::draw_panel(pencil, &lines, None, center)
+            }
+        }
+    }
+}
+
+// Feeds one tick of input to the game and advances it by one tick.
+pub fn step<G: Game>(game: &mut G, input: &Input) {
+    if let Some(win_size) = input.win_size {
+        game.resize(win_size);
+    }
+    for event in &input.events {
+        game.handle_action_event(event);
+    }
+    game.handle_actions_down(&input.actions_down);
+    game.update();
+}
+
+// Whether the window is too small for the game to be played in.
+pub fn is_too_small<G: Game>(win_size: Vec2) -> bool {
+    win_size.x < G::MIN_WINDOW.x || win_size.y < G::MIN_WINDOW.y
+}
+
+// Draws the game with its playfield centered in the canvas, with a border
+// around it if there is room for one.
+pub fn draw<G: Game>(game: &G, canvas: &mut Canvas) {
+    let win_size = canvas.dimension();
+    let dimension = game.dimension();
+    let mut pencil = Pencil::new(canvas);
+    pencil.set_origin((win_size - dimension) / 2);
+    if G::BORDER && win_size.x >= dimension.x + 2 && win_size.y >= dimension.y + 2 {
+        pencil
+            .set_foreground(Color::DarkGrey)
+            .draw_rect(&RectCharset::simple_lines(), Vec2::xy(-1, -1), dimension + Vec2::xy(2, 2))
+            .set_foreground(Color::White);
+    }
+    game.render(&mut pencil);
+}
+
+// Draws what is shown instead of the game while the window is too small.
+fn draw_too_small(pencil: &mut Pencil, min_window: Vec2, win_size: Vec2) {
+    let lines = [
+        "terminal too small".to_string(),
+        format!("{}x{} needed, {}x{} now", min_window.x, min_window.y, win_size.x, win_size.y),
+    ];
+    pencil.set_foreground(Color::Yellow);
+    for (index, line) in lines.iter().enumerate() {
+        let position = Vec2::xy((win_size.x - line.len() as i32) / 2, win_size.y / 2 - 1 + index as i32);
+        pencil.draw_text(line, position);
+    }
+}
+
+// Runs the game in the terminal until the player quits it. The game is
+// updated `G::TICKS_PER_SECOND` times per second whatever the frame rate,
+// and it is frozen while the pause menu is open.
+// The input of each tick is given by `next_input` from the keyboard input
+// for that tick, and it returns None once there is no more input.
+// When `keep_scores` is set, the high-score table of the game is updated and
+// shown once it is over.
+// Returns true if the player asked for the game to be restarted.
+fn play<G, F>(
+    app: &mut App,
+    state: &mut G,
+    seed: u64,
+    keep_scores: bool,
+    settings: &mut Settings,
+    bindings: &mut Bindings,
+    mut next_input: F,
+) -> bool
+where
+    G: Game,
+    F: FnMut(Input) -> Option<Input>,
+{
+    let mut fps_counter = FPSCounter::default();
+    let mut clock = Clock::new(G::TICKS_PER_SECOND);
+    let mut keyboard_input = Input::default();
+    let mut high_scores = if keep_scores { Some(HighScores::load(G::NAME)) } else { None };
+    let mut game_over: Option<GameOver> = None;
+    let mut paused: Option<PauseMenu> = None;
+    let mut restart = false;
+    let mut last_win_size = app.window().size();
+
+    app.run(|app_state: &mut State, window: &mut Window| {
+        // Let the game lay itself out again on the next tick if the window
+        // was resized
+        let win_size = window.size();
+        if win_size != last_win_size {
+            last_win_size = win_size;
+            keyboard_input.win_size = Some(win_size);
+        }
+        let too_small = is_too_small::<G>(win_size);
+
+        // Once the game is finished, ask for the initials of the player if the
+        // score made it into the high-score table, then show the table.
+        if state.is_finished() && game_over.is_none() {
+            paused = None;
+            game_over = Some(match &high_scores {
+                Some(table) if table.qualifies(state.score()) => GameOver::EnteringInitials(
+                    HighScore::new("", state.score(), seed, &state.mode()),
+                ),
+                _ => GameOver::ShowingScores(None),
+            });
+        }
+
+        let was_paused = paused.is_some();
+        for key_event in app_state.keyboard().last_key_events().iter().copied() {
+            if let Some(menu) = &mut paused {
+                match menu.handle_key_event(key_event, settings, bindings) {
+                    Some(PauseAction::Resume) => paused = None,
+                    Some(PauseAction::Restart) => {
+                        restart = true;
+                        app_state.stop();
+                    }
+                    Some(PauseAction::Quit) => app_state.stop(),
+                    None => (),
+                }
+                continue;
+            }
+
+            match game_over.take() {
+                // Pause the game if the user presses a pause key.
+                None => {
+                    if bindings.event(key_event) == Some(ActionEvent::Pressed(Action::Pause)) {
+                        paused = Some(PauseMenu::new());
+                    }
+                }
+                // Start the game over straight from the high scores
+                Some(GameOver::ShowingScores(_)) if matches!(key_event, KeyEvent::Pressed(Key::R)) => {
+                    restart = true;
+                    app_state.stop();
+                }
+                Some(current) => {
+                    game_over = current.handle_key_event(key_event, &mut high_scores);
+                    if game_over.is_none() {
+                        app_state.stop();
+                    }
+                }
+            }
+        }
+
+        // Run as many ticks as the time since the last frame is worth, unless
+        // the game is paused (or the window is too small to play in). The keys
+        // of a frame in which the menu was open all went to the menu, even
+        // the one that closed it, so the game starts again on the next frame.
+        if too_small || was_paused || paused.is_some() {
+            clock.reset();
+        } else if !state.is_finished() {
+            keyboard_input.read_keyboard(app_state.keyboard(), bindings);
+            for _ in 0..clock.ticks() {
+                if state.is_finished() {
+                    break;
+                }
+                if let Some(input) = next_input(keyboard_input.take_tick()) {
+                    step(state, &input);
+                }
+            }
+        }
+
+        fps_counter.update();
+
+        if too_small {
+            draw_too_small(&mut Pencil::new(window.canvas_mut()), G::MIN_WINDOW, win_size);
+            return;
+        }
+
+        draw(state, window.canvas_mut());
+        let mut pencil = Pencil::new(window.canvas_mut());
+        if settings.show_fps {
+            pencil.draw_text(&format!("FPS: {}", fps_counter.count()), Vec2::xy(1, 0));
+        }
+        if settings.show_seed {
+            pencil
+                .set_foreground(Color::DarkGrey)
+                .draw_text(&format!("seed: {}", seed), Vec2::xy(1, win_size.y - 1));
+        }
+        if let Some(game_over) = &game_over {
+            game_over.draw(&mut pencil, &high_scores, &state.summary(), win_size / 2);
+        }
+        if let Some(menu) = &paused {
+            menu.draw(&mut pencil, settings, bindings, win_size / 2);
+        }
+    });
+
+    restart
+}
+
+// Plays a game from the keyboard until the player quits it, starting it
+// over every time the player restarts it. Every tick is recorded and saved
+// as a replay if the options ask for it.
+pub fn run<G: Game>(options: &Options) {
+    let mut settings = Settings::default();
+    let mut bindings = Bindings::load(G::NAME, G::BINDINGS);
+    let level = options.level.as_ref().and_then(|path| match fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(error) => {
+            log::warn!("could not read the level {}: {}", path.display(), error);
+            None
+        }
+    });
+    loop {
+        let mut app = App::default();
+        let seed = options.game_seed();
+        let win_size = app.window().size();
+        let mut state = G::new(win_size, seed);
+        let mut recording = Replay::new(G::NAME, seed, win_size);
+        if let Some(text) = &level {
+            if let Err(error) = state.load_level(text) {
+                log::warn!("could not load the level of {}: {}", G::NAME, error);
+            }
+            recording.level = Some(text.clone());
+        }
+
+        let restart = play(&mut app, &mut state, seed, true, &mut settings, &mut bindings, |input| {
+            if options.record.is_some() {
+                recording.steps.push(input.clone());
+            }
+            Some(input)
+        });
+
+        if let Some(path) = &options.record {
+            if let Err(error) = recording.save(path) {
+                log::warn!("could not save the replay to {}: {}", path.display(), error);
+            }
+        }
+
+        if !restart {
+            break;
+        }
+    }
+}
+
+// Plays a game again from a replay instead of the keyboard. Restarting it
+// plays the replay again from the start.
+pub fn replay<G: Game>(replay: &Replay) {
+    let mut settings = Settings::default();
+    let mut bindings = Bindings::load(G::NAME, G::BINDINGS);
+    loop {
+        let mut app = App::default();
+        let mut state = G::new(replay.win_size, replay.seed);
+        if let Some(text) = &replay.level {
+            if let Err(error) = state.load_level(text) {
+                log::warn!("could not load the level of the replay: {}", error);
+            }
+        }
+        let mut steps = replay.steps.iter();
+
+        let next_input = |_: Input| steps.next().cloned();
+        if !play(&mut app, &mut state, replay.seed, false, &mut settings, &mut bindings, next_input) {
+            break;
+        }
+    }
+}
@@ -1,91 +1,1113 @@
-use ruscii::app::{App, State};
-use ruscii::drawing::Pencil;
-use ruscii::gui::FPSCounter;
-use ruscii::keyboard::{Key, KeyEvent};
+pub mod editor;
+pub mod levels;
+pub mod powerups;
+
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_6};
+use std::io;
+
+use ruscii::drawing::{Pencil, RectCharset};
+use ruscii::keyboard::Key;
 use ruscii::spatial::Vec2;
-use ruscii::terminal::{Color, Style, Window};
+use ruscii::terminal::Color;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-use rand::{self, prelude::*};
+use crate::bindings::{Action, ActionEvent};
+use crate::collision::{self, Hit, Rect};
+use crate::game::Game;
+use crate::physics::Vec2f;
 
+use self::levels::{Level, LEVEL_WIDTH};
+use self::powerups::{Capsule, Effect, PowerUp};
 
-struct GameState {
-    pub dimension: Vec2,
-    pub bouncer: Vec2,
-    pub last_shot_frame: usize,
-    pub bricks: Vec<Vec2>,
-    pub bricks_shots: Vec<Vec2>,
-    pub ball_movement: (i32, bool),
-    pub last_ball_movement: usize,
-    pub last_bricks_shots: usize,
+const MAX_MISSES : usize = 3;
+
+// The bricks take the 20 rows under the score and need to be at least 4
+// cells wide
+const MIN_WINDOW: Vec2 = Vec2 { x: 40, y: 26 };
+// The row of the top bricks, leaving the first one to the score
+const BRICKS_TOP: i32 = 1;
+
+// Speeds of the ball, in cells per tick
+const BALL_SPEED: f32 = 1.4;
+const MAX_BALL_SPEED: f32 = 2.0;
+// How much faster the ball gets every time it bounces off the bouncer
+const BALL_SPEED_RAMP: f32 = 1.03;
+// The angle from vertical the ball leaves at when it hits the edge of the bouncer
+const MAX_BOUNCE_ANGLE: f32 = FRAC_PI_3;
+// How many times the ball can bounce within a single tick
+const MAX_BOUNCES_PER_TICK: usize = 4;
+// How much the slow ball power-up slows the balls down
+const SLOW_BALL: f32 = 0.6;
+// The angle between the balls a multiball splits into
+const MULTIBALL_ANGLE: f32 = FRAC_PI_6;
+// How many balls can be in play at once
+const MAX_BALLS: usize = 12;
+// Speed of the laser shots, in cells per tick
+const LASER_SPEED: f32 = 1.5;
+// How long the game stops for after a life is lost or a level is cleared, in ticks
+const LIFE_LOST_TICKS: u32 = 45;
+const LEVEL_CLEAR_TICKS: u32 = 90;
+
+// Launches the ball upwards, between 30 and 60 degrees from vertical.
+pub fn random_ball_direction(rng: &mut StdRng) -> Vec2f {
+    let neg_x: bool = rng.gen();
+    let angle = rng.gen_range(FRAC_PI_6..FRAC_PI_3);
+    Vec2f::xy(if neg_x { -angle.sin() } else { angle.sin() }, -angle.cos()) * BALL_SPEED
+}
+
+/*
+    PlayerState defines the state of the player's bouncer. 
+    - its current position
+    - which direction it is moving
+    - how many shots it has missed
+    - how many extra lives it has picked up
+    - the power-ups it is under
+*/
+pub struct PlayerState {
+    pub position: Vec2,
+    pub direction: i32,
     pub misses: usize,
+    pub extra_lives: usize,
+    pub effects: Vec<Effect>,
+}
+
+impl PlayerState {
+    pub fn new(position: Vec2) -> PlayerState {
+        PlayerState {
+            position,
+            direction: 0,
+            misses: 0,
+            extra_lives: 0,
+            effects: Vec::new(),
+        }
+    }
+
+    pub fn lives_left(&self) -> usize {
+        (MAX_MISSES + self.extra_lives).saturating_sub(self.misses)
+    }
+
+    pub fn has(&self, power_up: PowerUp) -> bool {
+        self.effects.iter().any(|effect| effect.power_up == power_up)
+    }
+
+    // Puts the bouncer under a power-up for `ticks` ticks. Catching one it
+    // is already under starts it over.
+    pub fn add_effect(&mut self, power_up: PowerUp, ticks: u32) {
+        self.effects.retain(|effect| effect.power_up != power_up);
+        self.effects.push(Effect { power_up, ticks_left: ticks });
+    }
+
+    // Counts down the power-ups and returns those that ran out.
+    pub fn tick_effects(&mut self) -> Vec<PowerUp> {
+        let mut expired = Vec::new();
+        self.effects.retain_mut(|effect| {
+            effect.ticks_left = effect.ticks_left.saturating_sub(1);
+            if effect.ticks_left == 0 {
+                expired.push(effect.power_up);
+            }
+            effect.ticks_left > 0
+        });
+        expired
+    }
+
+    pub fn move_x(&mut self) {
+        self.position.x += self.direction;
+    }
+}
+
+/**
+ * BallState defines the state of a ball.
+ * - its current position (between cells)
+ * - its current velocity, in cells per tick
+ * - where it sits on the bouncer from its left end, if it was caught
+ */
+#[derive(Clone, Debug)]
+pub struct BallState {
+    pub position: Vec2f,
+    pub velocity: Vec2f,
+    pub stuck: Option<f32>,
+}
+
+
+impl BallState {
+    pub fn new(position: Vec2, rng: &mut StdRng) -> BallState {
+        BallState {
+            position: Vec2f::from(position),
+            velocity: random_ball_direction(rng),
+            stuck: None,
+        }
+    }
+
+    pub fn bounce_x(&mut self) {
+        self.velocity.x *= -1.0;
+    }
+
+    pub fn bounce_y(&mut self) {
+        self.velocity.y *= -1.0;
+    }
+
+    // Sends the ball back up at an angle that depends on where it hit the
+    // bouncer, from -1 (left edge) to 1 (right edge), a bit faster each time.
+    pub fn deflect(&mut self, offset: f32) {
+        let angle = offset.clamp(-1.0, 1.0) * MAX_BOUNCE_ANGLE;
+        let speed = (self.velocity.length() * BALL_SPEED_RAMP).min(MAX_BALL_SPEED);
+        self.velocity = Vec2f::xy(angle.sin(), -angle.cos()) * speed;
+    }
+
+    // The same ball going off `angle` radians clockwise from this one.
+    pub fn split(&self, angle: f32) -> BallState {
+        let direction = self.velocity.y.atan2(self.velocity.x) + angle;
+        BallState {
+            position: self.position,
+            velocity: Vec2f::from_angle(direction, self.velocity.length()),
+            stuck: None,
+        }
+    }
+}
+
+// The kinds of bricks, which take different numbers of hits to break.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrickKind {
+    // Breaks on the first hit
+    Normal,
+    // Takes a few hits, fading as it is damaged
+    Hard,
+    // Never breaks
+    Steel,
+    // Breaks on the first hit, along with the bricks around it
+    Explosive,
+}
+
+impl BrickKind {
+    // How many hits it takes to break a brick of this kind.
+    pub fn hits(&self) -> u32 {
+        match self {
+            BrickKind::Hard => 3,
+            _ => 1,
+        }
+    }
+
+    // The points scored for breaking a brick of this kind.
+    pub fn score(&self) -> usize {
+        match self {
+            BrickKind::Normal => 1,
+            BrickKind::Hard => 5,
+            BrickKind::Steel => 0,
+            BrickKind::Explosive => 3,
+        }
+    }
+}
+
+/*
+    BrickState defines the state of a brick. 
+    - its current position
+    - what kind of brick it is
+    - how many more hits it takes to break it
+    - the color the level gave it, if any
+*/
+#[derive(Clone, Debug)]
+pub struct BrickState {
+    pub position: Vec2,
+    pub kind: BrickKind,
+    pub hits_left: u32,
+    pub paint: Option<Color>,
+}
+
+// The color of the normal bricks of a row that were not given one.
+pub fn row_color(row_num: usize) -> Color {
+    match row_num {
+        0..=1 => Color::Red,
+        2..=3 => Color::Xterm(166),
+        4..=5 => Color::Green,
+        6..=7 => Color::Yellow,
+        _ => Color::DarkGrey,
+    }
+}
+
+impl BrickState {
+    pub fn new(position: Vec2, kind: BrickKind, paint: Option<Color>) -> BrickState {
+        BrickState {
+            position,
+            kind,
+            hits_left: kind.hits(),
+            paint,
+        }
+    }
+
+    // The color of the brick. Normal bricks take the color of their row
+    // unless the level gave them one.
+    pub fn color(&self, row_num: usize) -> Color {
+        match self.kind {
+            BrickKind::Normal => self.paint.unwrap_or_else(|| row_color(row_num)),
+            BrickKind::Hard => match self.hits_left {
+                3 => Color::White,
+                2 => Color::Grey,
+                _ => Color::DarkGrey,
+            },
+            BrickKind::Steel => Color::Grey,
+            BrickKind::Explosive => Color::Magenta,
+        }
+    }
+}
+
+// What is going on in a game of Breakout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    // The ball sits on the bouncer until it is fired
+    Serving,
+    Playing,
+    // The last ball was lost: the game stops for this many more ticks
+    LifeLost(u32),
+    // Every brick was broken: the next level starts after this many more ticks
+    LevelClear(u32),
+    // No lives are left
+    GameOver,
+}
+
+pub struct GameState {
+    pub dimension: Vec2,
+    pub phase: Phase,
+    pub bouncer: PlayerState,
+    pub bricks: Vec<Vec<BrickState>>,
+    pub balls: Vec<BallState>,
+    pub capsules: Vec<Capsule>,
+    pub lasers: Vec<Vec2f>,
+    pub levels: Vec<Level>,
+    pub level: usize,
     pub score: usize,
+    pub rng: StdRng,
+}
+
+// Draws a brick of the given size, in the color of its row of bricks.
+pub fn draw_brick(pencil: &mut Pencil, brick: &BrickState, row_num: usize, size: Vec2) {
+    pencil.set_foreground(brick.color(row_num));
+    let charset = match brick.kind {
+        BrickKind::Steel => RectCharset::double_lines(),
+        _ => RectCharset::simple_lines(),
+    };
+    pencil.draw_rect(&charset, brick.position, size);
+    if brick.kind == BrickKind::Explosive {
+        pencil.draw_char('*', brick.position + Vec2::x(size.x / 2));
+    }
+}
+
+// Lays out the bricks of a level across a window of the given size. Every
+// column of the level is kept as a row of `bricks`, which gives normal bricks
+// their color.
+fn build_bricks(level: &Level, dimension: Vec2) -> Vec<Vec<BrickState>> {
+    let brick_step = dimension.x / 20 * 2;
+    let mut bricks = vec![Vec::new(); LEVEL_WIDTH];
+    for (x, y, kind, paint) in level.bricks() {
+        let position = Vec2::xy(x as i32 * brick_step, BRICKS_TOP + y as i32 * 2);
+        bricks[x].push(BrickState::new(position, kind, paint));
+    }
+    bricks
+}
+
+// The column and row on the level grid of the brick at `position`.
+fn grid_cell(position: Vec2, dimension: Vec2) -> Vec2 {
+    let brick_step = (dimension.x / 20 * 2).max(1);
+    Vec2::xy(position.x / brick_step, (position.y - BRICKS_TOP) / 2)
 }
 
 
 impl GameState {
+    // The size of the bouncer and of every brick
+    pub fn block_size(&self) -> Vec2 {
+        Vec2::xy(self.dimension.x / 10, 2)
+    }
+
+    // The size of the bouncer, which grows under the wide power-up
+    pub fn bouncer_size(&self) -> Vec2 {
+        let size = self.block_size();
+        if self.bouncer.has(PowerUp::Wide) {
+            Vec2::xy(size.x * 3 / 2, size.y)
+        } else {
+            size
+        }
+    }
+
+    // Hits a brick, breaking it if it has no hits left (unless it is steel).
+    fn hit_brick(&mut self, row_index: usize, index: usize) {
+        let brick = &mut self.bricks[row_index][index];
+        if brick.kind == BrickKind::Steel {
+            return;
+        }
+        brick.hits_left = brick.hits_left.saturating_sub(1);
+        if brick.hits_left > 0 {
+            return;
+        }
+
+        let brick = self.bricks[row_index].remove(index);
+        self.break_brick(&brick);
+    }
+
+    // Scores a brick that was just broken, which may drop a capsule. An
+    // explosive brick takes the bricks around it with it.
+    fn break_brick(&mut self, brick: &BrickState) {
+        self.score += brick.kind.score();
+        if let Some(power_up) = PowerUp::random_drop(&mut self.rng) {
+            let center = Vec2f::from(brick.position + self.block_size() / 2);
+            self.capsules.push(Capsule::new(center, power_up));
+        }
+        if brick.kind == BrickKind::Explosive {
+            self.explode(brick.position);
+        }
+    }
+
+    // Breaks every brick next to the one at `center` on the level grid (even
+    // diagonally), whatever hits they have left, except steel ones.
+    fn explode(&mut self, center: Vec2) {
+        let dimension = self.dimension;
+        let center = grid_cell(center, dimension);
+        let mut broken = Vec::new();
+        for row in self.bricks.iter_mut() {
+            row.retain(|brick| {
+                let cell = grid_cell(brick.position, dimension);
+                let caught = brick.kind != BrickKind::Steel
+                    && (cell.x - center.x).abs() <= 1
+                    && (cell.y - center.y).abs() <= 1;
+                if caught {
+                    broken.push(brick.clone());
+                }
+                !caught
+            });
+        }
+
+        for brick in broken {
+            self.break_brick(&brick);
+        }
+    }
+
+    pub fn bouncer_move_x(&mut self, direction: i32) {
+        if (self.bouncer.position.x - 3 < 0 && direction < 0) 
+            || (self.bouncer.position.x + 3 > self.dimension.x && direction > 0) {
+            self.bouncer.direction = 0;
+        } else {
+            self.bouncer.direction = direction;
+        }
+    }
 
-    pub fn new(dimension: Vec2) -> GameState {
-        let mut bricks = Vec::new();
-        for y in 2..10 {
-            for x in 5..dimension.x - 5 {
-                if x % 2 != 0 {
-                    aliens.push(Vec2::xy(x, y));
+    // Finds the first brick in the way of something moving from `start`
+    // along `motion`, given by its row and index.
+    fn first_brick_hit(&self, start: Vec2f, motion: Vec2f) -> Option<(Hit, usize, usize)> {
+        let size = self.block_size();
+        let mut first: Option<(Hit, usize, usize)> = None;
+        for (row_index, row) in self.bricks.iter().enumerate() {
+            for (index, brick) in row.iter().enumerate() {
+                let rect = Rect::from_cells(brick.position, size);
+                if let Some(hit) = collision::sweep_point(start, motion, &rect) {
+                    if !first.is_some_and(|(first_hit, _, _)| first_hit.time <= hit.time) {
+                        first = Some((hit, row_index, index));
+                    }
                 }
             }
         }
+        first
+    }
 
-        GameState {
-            dimension,
-            bouncer: Vec2::xy(dimension.x / 2, dimension.y - 2),
-            last_shot_frame: 0,
-            bricks: bricks,
-            bricks_shots: Vec::new(),
-            ball_movement: (1, false),
-            last_ball_movement: 0,
-            last_bricks_shots: 0,
-            misses: 0,
-            score: 0,
+    // Moves a ball along its velocity, bouncing it off the bouncer and the
+    // bricks in its way. What is left of the motion carries on after each
+    // bounce, so that a fast ball never goes through anything. A ball the
+    // bouncer caught stays where it is.
+    fn move_ball(&mut self, ball_index: usize) {
+        if self.balls[ball_index].stuck.is_some() {
+            return;
+        }
+        let bouncer_size = self.bouncer_size();
+        let bouncer = Rect::from_cells(self.bouncer.position, bouncer_size);
+        let mut remaining = if self.bouncer.has(PowerUp::Slow) { SLOW_BALL } else { 1.0 };
+
+        for _ in 0..MAX_BOUNCES_PER_TICK {
+            let ball = &self.balls[ball_index];
+            let motion = ball.velocity * remaining;
+
+            // Find the first thing in the way of the ball: the bouncer (only
+            // on the way down) or a brick, given by its row and index
+            let mut first: Option<(Hit, Option<(usize, usize)>)> = None;
+            if ball.velocity.y > 0.0 {
+                first = collision::sweep_point(ball.position, motion, &bouncer)
+                    .map(|hit| (hit, None));
+            }
+            if let Some((hit, row_index, index)) = self.first_brick_hit(ball.position, motion) {
+                if !first.is_some_and(|(first_hit, _)| first_hit.time <= hit.time) {
+                    first = Some((hit, Some((row_index, index))));
+                }
+            }
+
+            let Some((hit, brick)) = first else {
+                self.balls[ball_index].position += motion;
+                return;
+            };
+
+            self.balls[ball_index].position = hit.position;
+            remaining *= 1.0 - hit.time;
+            match brick {
+                Some((row_index, index)) => {
+                    self.hit_brick(row_index, index);
+                    if hit.side.is_vertical() {
+                        self.balls[ball_index].bounce_x();
+                    } else {
+                        self.balls[ball_index].bounce_y();
+                    }
+                }
+                // Deflect the ball depending on how far from the middle of
+                // the bouncer it hit, unless it only hit one of its ends. A
+                // sticky bouncer holds on to it until it is fired.
+                None if hit.side.is_vertical() => self.balls[ball_index].bounce_x(),
+                None => {
+                    let half_width = bouncer_size.x as f32 / 2.0;
+                    let center = bouncer.position.x + half_width;
+                    let ball = &mut self.balls[ball_index];
+                    ball.deflect((hit.position.x - center) / half_width);
+                    if self.bouncer.has(PowerUp::Sticky) {
+                        ball.stuck = Some(hit.position.x - bouncer.position.x);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    // Keeps the balls the bouncer caught on top of it.
+    fn carry_stuck_balls(&mut self) {
+        let width = self.bouncer_size().x as f32;
+        let left = self.bouncer.position.x as f32 - 0.5;
+        let top = self.bouncer.position.y as f32 - 1.0;
+        for ball in self.balls.iter_mut() {
+            if let Some(offset) = ball.stuck.as_mut() {
+                *offset = offset.clamp(0.0, width);
+                ball.position = Vec2f::xy(left + *offset, top);
+            }
+        }
+    }
+
+    fn release_balls(&mut self) {
+        for ball in self.balls.iter_mut() {
+            ball.stuck = None;
         }
     }
 
-    pub fn random_ball_direction() -> Vec2 {
-        let mut rng = rand::thread_rng();
-        let neg_x: bool = rng.gen();
-        let neg_y: bool = rng.gen();
-        Vec2::xy(if neg_x { -1 } else { 1 }, if neg_y { -1 } else { 1 })
+    // Fires a laser shot from each end of the bouncer.
+    fn fire_lasers(&mut self) {
+        let width = self.bouncer_size().x;
+        let position = self.bouncer.position - Vec2::y(1);
+        self.lasers.push(Vec2f::from(position));
+        self.lasers.push(Vec2f::from(position + Vec2::x(width - 1)));
+    }
+
+    // Moves the laser shots up, breaking the first brick each one reaches.
+    fn move_lasers(&mut self) {
+        let motion = Vec2f::xy(0.0, -LASER_SPEED);
+        let mut lasers = std::mem::take(&mut self.lasers);
+        lasers.retain_mut(|laser| match self.first_brick_hit(*laser, motion) {
+            Some((_, row_index, index)) => {
+                self.hit_brick(row_index, index);
+                false
+            }
+            None => {
+                *laser += motion;
+                laser.y >= 0.0
+            }
+        });
+        self.lasers = lasers;
     }
 
-    pub fn update(&mut self, frame: usize) {
+    // Drops the capsules, giving the bouncer those it catches.
+    fn move_capsules(&mut self) {
+        let bouncer = Rect::from_cells(self.bouncer.position, self.bouncer_size());
+        let mut caught = Vec::new();
+        let bottom = self.dimension.y as f32;
+        self.capsules.retain_mut(|capsule| {
+            capsule.fall();
+            if bouncer.contains(capsule.position) {
+                caught.push(capsule.power_up);
+                return false;
+            }
+            capsule.position.y < bottom
+        });
 
+        for power_up in caught {
+            self.apply_power_up(power_up);
+        }
     }
 
-  
+    fn apply_power_up(&mut self, power_up: PowerUp) {
+        match power_up {
+            PowerUp::Multiball => {
+                let mut split = Vec::new();
+                for ball in &self.balls {
+                    split.push(ball.split(-MULTIBALL_ANGLE));
+                    split.push(ball.split(MULTIBALL_ANGLE));
+                }
+                let room = MAX_BALLS.saturating_sub(self.balls.len());
+                self.balls.extend(split.into_iter().take(room));
+            }
+            PowerUp::ExtraLife => self.bouncer.extra_lives += 1,
+            _ => {
+                if let Some(ticks) = power_up.duration() {
+                    self.bouncer.add_effect(power_up, ticks);
+                }
+                // Keep a wider bouncer within the window
+                let width = self.bouncer_size().x;
+                let max_x = (self.dimension.x - width).max(0);
+                self.bouncer.position.x = self.bouncer.position.x.min(max_x);
+            }
+        }
+    }
+
+    // Counts down the power-ups, letting go of the balls when the bouncer
+    // stops being sticky.
+    fn tick_effects(&mut self) {
+        let expired = self.bouncer.tick_effects();
+        if expired.contains(&PowerUp::Sticky) {
+            self.release_balls();
+        }
+    }
+
+    // Puts a new ball on the middle of the bouncer, to be fired by the player.
+    fn serve(&mut self) {
+        let mut ball = BallState::new(self.bouncer.position, &mut self.rng);
+        ball.stuck = Some(self.bouncer_size().x as f32 / 2.0);
+        self.balls = vec![ball];
+        self.carry_stuck_balls();
+        self.phase = Phase::Serving;
+    }
+
+    // Lays out the bricks of a level and serves a new ball. The power-ups
+    // the bouncer is under carry on into the level.
+    fn start_level(&mut self, level: usize) {
+        self.level = level;
+        self.bricks = build_bricks(&self.levels[level], self.dimension);
+        self.capsules.clear();
+        self.lasers.clear();
+        self.serve();
+    }
+
+    pub fn is_level_clear(&self) -> bool {
+        self.bricks.iter().flatten().all(|brick| brick.kind == BrickKind::Steel)
+    }
+
+    // Takes away a life once every ball is lost. The power-ups go with it.
+    fn lose_ball(&mut self) {
+        self.bouncer.misses += 1;
+        self.bouncer.effects.clear();
+        self.capsules.clear();
+        self.lasers.clear();
+        self.phase = Phase::LifeLost(LIFE_LOST_TICKS);
+    }
+
+    // Advances the game by one tick while the ball is in play.
+    fn play(&mut self) {
+        self.tick_effects();
+        self.carry_stuck_balls();
+    
+        // Move the balls
+        for index in 0..self.balls.len() {
+            // 1. Ensure that the ball is within bounds: a wall only sends it
+            // back when it is heading into it, so that it cannot get stuck
+            // there, and a ball in a corner bounces off both walls
+            let ball = &mut self.balls[index];
+            if ball.stuck.is_some() {
+                continue;
+            }
+            let right = (self.dimension.x - 1) as f32;
+            if ball.position.x <= 1.0 && ball.velocity.x < 0.0 {
+                ball.position.x = 1.0;
+                ball.bounce_x();
+            }
+            if ball.position.x >= right && ball.velocity.x > 0.0 {
+                ball.position.x = right;
+                ball.bounce_x();
+            }
+            if ball.position.y <= 1.0 && ball.velocity.y < 0.0 {
+                ball.position.y = 1.0;
+                ball.bounce_y();
+            }
+
+            // 2. Move the ball, bouncing it off the bouncer and the bricks
+            self.move_ball(index);
+        }
+
+        // Move the laser shots and the capsules
+        self.move_lasers();
+        self.move_capsules();
+
+        // 3. Drop the balls that are out of bounds
+        // (if none are left then decrease the number of lives left)
+        let bottom = (self.dimension.y + 10) as f32;
+        self.balls.retain(|ball| ball.position.y <= bottom);
+        if self.balls.is_empty() {
+            self.lose_ball();
+            return;
+        }
+
+        // 4. Stop once every brick that can break is gone
+        if self.is_level_clear() {
+            self.capsules.clear();
+            self.lasers.clear();
+            self.phase = Phase::LevelClear(LEVEL_CLEAR_TICKS);
+        }
+    }
+
+    // The message shown between the bricks and the bouncer, if any.
+    fn message(&self) -> Option<String> {
+        match self.phase {
+            Phase::Serving => Some("space: launch the ball".to_string()),
+            Phase::Playing => None,
+            Phase::LifeLost(_) => Some(match self.bouncer.lives_left() {
+                0 => "ball lost - no lives left".to_string(),
+                1 => "ball lost - 1 life left".to_string(),
+                lives => format!("ball lost - {} lives left", lives),
+            }),
+            Phase::LevelClear(_) => Some(format!("level {} cleared!", self.level + 1)),
+            Phase::GameOver => Some(format!("game over  -  score: {}", self.score)),
+        }
+    }
 }
 
+impl Game for GameState {
+    const NAME: &'static str = "Breakout";
+    const TICKS_PER_SECOND: u32 = 30;
+    const MIN_WINDOW: Vec2 = MIN_WINDOW;
+    const BINDINGS: &'static [(Action, &'static [Key])] = &[
+        (Action::MoveLeft, &[Key::A, Key::J, Key::Left]),
+        (Action::MoveRight, &[Key::D, Key::L, Key::Right]),
+        (Action::Fire, &[Key::Space, Key::K, Key::Up]),
+    ];
 
-fn main() {
-    let mut fps_counter = FPSCounter::default();
-    let mut app = App::default();
-    let win_size = app.window().size();
-    let mut state = GameState::new((win_size * 4) / 5);
+    fn new(dimension: Vec2, seed: u64) -> GameState {
+        let rng = StdRng::seed_from_u64(seed);
 
-    app.run(|app_state: &mut State, window: &mut Window| {
-        for key_event in app_state.keyboard().last_key_events() {
-            match key_event {
-                KeyEvent::Pressed(Key::Esc) => app_state.stop(),
-                KeyEvent::Pressed(Key::Q) => app_state.stop(),
-                _ => (),
+        // Create the bricks of the first level relative to the size of the window
+        let levels = Level::built_in();
+        let bricks = build_bricks(&levels[0], dimension);
+
+        let mut state = GameState {
+            dimension,
+            phase: Phase::Serving,
+            bouncer: PlayerState::new(Vec2::xy(dimension.x / 2 + 10, dimension.y - 2)),
+            bricks,
+            balls: Vec::new(),
+            capsules: Vec::new(),
+            lasers: Vec::new(),
+            levels,
+            level: 0,
+            score: 0,
+            rng,
+        };
+        state.serve();
+        state
+    }
+
+    // A custom level is played over and over instead of the built-in ones.
+    fn load_level(&mut self, text: &str) -> io::Result<()> {
+        self.levels = vec![Level::parse(text)?];
+        self.start_level(0);
+        Ok(())
+    }
+
+    fn dimension(&self) -> Vec2 {
+        self.dimension
+    }
+
+    // Breakout always fills the window: the bricks keep their rows and are
+    // spread over the new width, and the bouncer and everything moving keep
+    // their place relative to the size of the window.
+    fn resize(&mut self, win_size: Vec2) {
+        let old = self.dimension;
+        let old_brick_step = (old.x / 20 * 2).max(1);
+        let new_brick_step = win_size.x / 20 * 2;
+        for row in self.bricks.iter_mut() {
+            for brick in row.iter_mut() {
+                brick.position.x = brick.position.x / old_brick_step * new_brick_step;
             }
         }
 
-        for key_down in app_state.keyboard().get_keys_down() {
-            match key_down {
-                Key::W => state.left_player.direction = -1,
-                Key::S => state.left_player.direction = 1,
-                Key::O => state.right_player.direction = -1,
-                Key::L => state.right_player.direction = 1,
+        let scale_x = win_size.x as f32 / old.x.max(1) as f32;
+        self.bouncer.position = Vec2::xy(
+            (self.bouncer.position.x * win_size.x / old.x.max(1)).min(win_size.x - 3),
+            win_size.y - 2,
+        );
+        for ball in self.balls.iter_mut() {
+            ball.position.x *= scale_x;
+            ball.position.y = ball.position.y.min((win_size.y - 3) as f32);
+        }
+        for capsule in self.capsules.iter_mut() {
+            capsule.position.x *= scale_x;
+        }
+        for laser in self.lasers.iter_mut() {
+            laser.x *= scale_x;
+        }
+        self.dimension = win_size;
+        self.carry_stuck_balls();
+    }
+
+    // Fire launches the ball, lets go of the balls the bouncer caught, and
+    // shoots its lasers
+    fn handle_action_event(&mut self, event: &ActionEvent) {
+        if *event != ActionEvent::Pressed(Action::Fire) {
+            return;
+        }
+        if self.phase == Phase::Serving {
+            self.release_balls();
+            self.phase = Phase::Playing;
+            return;
+        }
+        if self.phase != Phase::Playing {
+            return;
+        }
+        if self.balls.iter().any(|ball| ball.stuck.is_some()) {
+            self.release_balls();
+        } else if self.bouncer.has(PowerUp::Laser) {
+            self.fire_lasers();
+        }
+    }
+
+    fn handle_actions_down(&mut self, actions: &[Action]) {
+        // Register the movement of the bouncer
+        let relative_speed = self.dimension.x / 50;
+        for action in actions {
+            match action {
+                Action::MoveLeft    => self.bouncer_move_x(-relative_speed),
+                Action::MoveRight   => self.bouncer_move_x(relative_speed),
                 _ => (),
             }
         }
-    });
-}
\ No newline at end of file
+    }
+
+    fn update(&mut self) {
+        match self.phase {
+            // The bouncer can be moved around with the ball on it
+            Phase::Serving => {
+                self.bouncer.move_x();
+                self.bouncer.direction = 0;
+                self.carry_stuck_balls();
+            }
+            Phase::Playing => {
+                self.bouncer.move_x();
+                self.bouncer.direction = 0;
+                self.play();
+            }
+            Phase::LifeLost(0) if self.bouncer.lives_left() == 0 => self.phase = Phase::GameOver,
+            Phase::LifeLost(0) => self.serve(),
+            Phase::LevelClear(0) => self.start_level((self.level + 1) % self.levels.len()),
+            Phase::LifeLost(ticks) => self.phase = Phase::LifeLost(ticks - 1),
+            Phase::LevelClear(ticks) => self.phase = Phase::LevelClear(ticks - 1),
+            Phase::GameOver => (),
+        }
+    }
+
+    fn render(&self, pencil: &mut Pencil) {
+
+        // Say what is going on, below the bricks
+        if let Some(msg) = self.message() {
+            pencil.set_foreground(Color::White);
+            let position = Vec2::xy((self.dimension.x - msg.len() as i32) / 2, self.dimension.y - 5);
+            pencil.draw_text(&msg, position);
+        }
+
+        // Draw the score and the lives left
+        let display_text = format!(
+            "level: {}  -  score: {}  -  lives: {}",
+            self.level + 1,
+            self.score,
+            self.bouncer.lives_left()
+        );
+        let display_x = self.dimension.x - display_text.len() as i32 - 1;
+        pencil.set_foreground(Color::Green);
+        pencil.draw_text(&display_text, Vec2::xy(display_x, 0));
+
+        // Draw the power-ups the bouncer is under, with the seconds they have
+        // left, on the left of the score (as many as there is room for)
+        let mut x = 1;
+        for effect in &self.bouncer.effects {
+            let seconds = effect.ticks_left.div_ceil(Self::TICKS_PER_SECOND);
+            let text = format!("{} {}", effect.power_up.name(), seconds);
+            if x + text.len() as i32 + 2 > display_x {
+                break;
+            }
+            pencil.set_foreground(effect.power_up.color());
+            pencil.draw_text(&text, Vec2::xy(x, 0));
+            x += text.len() as i32 + 2;
+        }
+
+        // Draw the bouncer
+        pencil.set_foreground(Color::Red);
+        pencil.draw_rect(&RectCharset::double_lines(), 
+                        self.bouncer.position, 
+                        self.bouncer_size());
+
+        // Draw the balls, the laser shots and the capsules
+        pencil.set_foreground(Color::Yellow);
+        for ball in &self.balls {
+            pencil.draw_char('0', ball.position.to_cell());
+        }
+        pencil.set_foreground(Color::Red);
+        for laser in &self.lasers {
+            pencil.draw_char('|', laser.to_cell());
+        }
+        for capsule in &self.capsules {
+            pencil.set_foreground(capsule.power_up.color());
+            pencil.draw_char(capsule.power_up.letter(), capsule.position.to_cell());
+        }
+
+        // Draw the bricks
+        for (row_num, row) in self.bricks.iter().enumerate() {
+            for brick in row.iter() {
+                draw_brick(pencil, brick, row_num, self.block_size());
+            }
+        }
+    }
+
+    fn score(&self) -> usize {
+        self.score
+    }
+
+    fn is_finished(&self) -> bool {
+        self.phase == Phase::GameOver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::Headless;
+
+    // A game whose only bricks are the given ones, at (column, row) of the
+    // brick grid. Its bricks are wider than the grid step, as in most windows.
+    fn board(bricks: &[(i32, i32, BrickKind)]) -> GameState {
+        let mut game = GameState::new(Vec2::xy(90, 40), 1);
+        let step = game.dimension.x / 20 * 2;
+        game.bricks = vec![bricks
+            .iter()
+            .map(|(column, row, kind)| {
+                BrickState::new(Vec2::xy(column * step, BRICKS_TOP + row * 2), *kind, None)
+            })
+            .collect()];
+        game
+    }
+
+    fn kinds(game: &GameState) -> Vec<BrickKind> {
+        game.bricks.iter().flatten().map(|brick| brick.kind).collect()
+    }
+
+    #[test]
+    fn hard_bricks_take_three_hits() {
+        let mut game = board(&[(0, 0, BrickKind::Hard)]);
+        game.hit_brick(0, 0);
+        game.hit_brick(0, 0);
+        assert_eq!(game.bricks[0][0].hits_left, 1);
+        assert_eq!(game.bricks[0][0].color(0), Color::DarkGrey);
+        assert_eq!(game.score, 0);
+
+        game.hit_brick(0, 0);
+        assert!(game.bricks[0].is_empty());
+        assert_eq!(game.score, 5);
+    }
+
+    #[test]
+    fn steel_bricks_never_break() {
+        let mut game = board(&[(0, 0, BrickKind::Steel)]);
+        for _ in 0..10 {
+            game.hit_brick(0, 0);
+        }
+        assert_eq!(kinds(&game), [BrickKind::Steel]);
+        assert_eq!(game.score, 0);
+    }
+
+    #[test]
+    fn explosions_break_the_bricks_around_except_steel() {
+        let mut game = board(&[
+            (2, 2, BrickKind::Explosive),
+            (1, 2, BrickKind::Normal),
+            (3, 3, BrickKind::Hard),
+            (2, 1, BrickKind::Steel),
+            (7, 2, BrickKind::Normal),
+        ]);
+        game.hit_brick(0, 0);
+        assert_eq!(kinds(&game), [BrickKind::Steel, BrickKind::Normal]);
+        assert_eq!(game.score, 3 + 1 + 5);
+    }
+
+    #[test]
+    fn explosions_only_reach_the_next_column_and_row() {
+        let mut game = board(&[
+            (5, 2, BrickKind::Explosive),
+            (3, 2, BrickKind::Normal),
+            (4, 2, BrickKind::Normal),
+            (6, 2, BrickKind::Normal),
+            (7, 2, BrickKind::Normal),
+            (5, 0, BrickKind::Normal),
+            (4, 3, BrickKind::Normal),
+            (6, 4, BrickKind::Normal),
+        ]);
+        game.hit_brick(0, 0);
+        let left: Vec<Vec2> = game
+            .bricks
+            .iter()
+            .flatten()
+            .map(|brick| grid_cell(brick.position, game.dimension))
+            .collect();
+        assert_eq!(left, [Vec2::xy(3, 2), Vec2::xy(7, 2), Vec2::xy(5, 0), Vec2::xy(6, 4)]);
+    }
+
+    #[test]
+    fn explosions_chain() {
+        let mut game = board(&[
+            (2, 2, BrickKind::Explosive),
+            (3, 2, BrickKind::Explosive),
+            (4, 2, BrickKind::Normal),
+            (9, 2, BrickKind::Normal),
+        ]);
+        game.hit_brick(0, 0);
+        assert_eq!(kinds(&game), [BrickKind::Normal]);
+        assert_eq!(game.score, 3 + 3 + 1);
+    }
+
+    #[test]
+    fn power_ups_are_shown_next_to_the_score() {
+        let mut headless: Headless<GameState> = Headless::new(Vec2::xy(80, 30), 1);
+        headless.game.apply_power_up(PowerUp::Wide);
+        headless.game.apply_power_up(PowerUp::Slow);
+        let top = &headless.frame().lines()[0];
+        assert!(top.starts_with(" wide 15  slow 15"), "{}", top);
+        assert!(top.contains("level: 1"), "{}", top);
+
+        // Those that do not fit before the score are left out
+        let mut headless: Headless<GameState> = Headless::new(MIN_WINDOW, 1);
+        headless.game.apply_power_up(PowerUp::Sticky);
+        let top = &headless.frame().lines()[0];
+        assert!(!top.contains("sticky"), "{}", top);
+        assert!(top.contains("level: 1  -  score: 0  -  lives: 3"), "{}", top);
+    }
+
+    #[test]
+    fn multiball_splits_every_ball_up_to_the_limit() {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        game.apply_power_up(PowerUp::Multiball);
+        assert_eq!(game.balls.len(), 3);
+        game.apply_power_up(PowerUp::Multiball);
+        assert_eq!(game.balls.len(), 9);
+        game.apply_power_up(PowerUp::Multiball);
+        assert_eq!(game.balls.len(), MAX_BALLS);
+    }
+
+    #[test]
+    fn caught_capsules_give_their_power_up_until_it_runs_out() {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        let width = game.bouncer_size().x;
+        let above = Vec2f::from(game.bouncer.position + Vec2::x(1)) - Vec2f::xy(0.0, 0.1);
+        game.capsules.push(Capsule::new(above, PowerUp::Wide));
+        game.move_capsules();
+        assert!(game.capsules.is_empty());
+        assert!(game.bouncer_size().x > width);
+
+        for _ in 0..PowerUp::Wide.duration().unwrap() {
+            game.tick_effects();
+        }
+        assert_eq!(game.bouncer_size().x, width);
+    }
+
+    fn fire(game: &mut GameState) {
+        game.handle_action_event(&ActionEvent::Pressed(Action::Fire));
+    }
+
+    #[test]
+    fn the_ball_waits_on_the_bouncer_until_fired() {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        assert_eq!(game.phase, Phase::Serving);
+        game.handle_actions_down(&[Action::MoveLeft]);
+        game.update();
+        assert!(game.balls[0].stuck.is_some());
+        assert_eq!(game.balls[0].position.y, game.bouncer.position.y as f32 - 1.0);
+
+        fire(&mut game);
+        assert_eq!(game.phase, Phase::Playing);
+        assert!(game.balls.iter().all(|ball| ball.stuck.is_none()));
+    }
+
+    #[test]
+    fn losing_the_last_ball_costs_a_life_and_serves_again() {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        fire(&mut game);
+        game.balls[0].position.y = 100.0;
+        game.update();
+        assert_eq!(game.phase, Phase::LifeLost(LIFE_LOST_TICKS));
+        assert_eq!(game.bouncer.lives_left(), MAX_MISSES - 1);
+
+        // Fire does nothing until the next ball is served
+        fire(&mut game);
+        for _ in 0..=LIFE_LOST_TICKS {
+            game.update();
+        }
+        assert_eq!(game.phase, Phase::Serving);
+        assert_eq!(game.balls.len(), 1);
+        assert!(game.balls[0].stuck.is_some());
+    }
+
+    #[test]
+    fn losing_the_last_life_ends_the_game() {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        game.bouncer.misses = MAX_MISSES - 1;
+        fire(&mut game);
+        game.balls[0].position.y = 100.0;
+        game.update();
+        assert!(!game.is_finished());
+        for _ in 0..=LIFE_LOST_TICKS {
+            game.update();
+        }
+        assert_eq!(game.phase, Phase::GameOver);
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn clearing_a_level_starts_the_next_one() {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        fire(&mut game);
+        game.bricks = vec![vec![BrickState::new(Vec2::xy(0, BRICKS_TOP), BrickKind::Steel, None)]];
+        game.update();
+        assert_eq!(game.phase, Phase::LevelClear(LEVEL_CLEAR_TICKS));
+        for _ in 0..=LEVEL_CLEAR_TICKS {
+            game.update();
+        }
+        assert_eq!(game.level, 1);
+        assert_eq!(game.phase, Phase::Serving);
+        assert!(!game.is_level_clear());
+    }
+
+    // A game in play with a single ball, and a single brick out of its way.
+    fn ball_at(position: Vec2f, velocity: Vec2f) -> GameState {
+        let mut game = GameState::new(Vec2::xy(80, 30), 1);
+        fire(&mut game);
+        game.bricks = vec![vec![BrickState::new(Vec2::xy(40, 15), BrickKind::Normal, None)]];
+        game.balls[0].position = position;
+        game.balls[0].velocity = velocity;
+        game
+    }
+
+    #[test]
+    fn balls_bounce_off_both_walls_of_a_corner() {
+        let mut game = ball_at(Vec2f::xy(0.5, 0.5), Vec2f::xy(-0.5, -0.5));
+        game.update();
+        assert_eq!(game.balls[0].velocity, Vec2f::xy(0.5, 0.5));
+        assert_eq!(game.balls[0].position, Vec2f::xy(1.5, 1.5));
+
+        let mut game = ball_at(Vec2f::xy(79.5, 0.5), Vec2f::xy(0.5, -0.5));
+        game.update();
+        assert_eq!(game.balls[0].velocity, Vec2f::xy(-0.5, 0.5));
+        assert_eq!(game.balls[0].position, Vec2f::xy(78.5, 1.5));
+    }
+
+    #[test]
+    fn balls_leaving_a_wall_are_not_sent_back() {
+        let mut game = ball_at(Vec2f::xy(0.8, 10.0), Vec2f::xy(0.5, 0.5));
+        game.update();
+        assert_eq!(game.balls[0].velocity, Vec2f::xy(0.5, 0.5));
+
+        let mut game = ball_at(Vec2f::xy(30.0, 0.8), Vec2f::xy(0.5, 0.5));
+        game.update();
+        assert_eq!(game.balls[0].velocity, Vec2f::xy(0.5, 0.5));
+    }
+}
@@ -0,0 +1,326 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ruscii::app::{App, State};
+use ruscii::drawing::{Pencil, RectCharset};
+use ruscii::keyboard::{Key, KeyEvent};
+use ruscii::spatial::Vec2;
+use ruscii::terminal::{Color, Style, Window};
+
+use super::levels::{self, Level, EMPTY, LEVEL_HEIGHT, LEVEL_WIDTH};
+use super::{draw_brick, BrickState};
+
+// The size of a brick in the editor (the smallest it gets in the game)
+const CELL: Vec2 = Vec2 { x: 4, y: 2 };
+// Where the grid is drawn in the editor: below the title, inside its border
+const GRID_ORIGIN: Vec2 = Vec2 { x: 1, y: 2 };
+const HELP: [&str; 3] = [
+    "arrows: move - space: place - x: erase",
+    "1-9, 0 or tab: pick a brick",
+    "s: save - o: load again - q: quit",
+];
+
+// The keys choosing the bricks, in the order of `levels::brick_chars`
+const BRUSH_KEYS: [Key; 10] = [
+    Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5,
+    Key::Num6, Key::Num7, Key::Num8, Key::Num9, Key::Num0,
+];
+
+/*
+    EditorState defines the state of the level editor.
+    - the level being edited and the file it is saved to
+    - the cell under the cursor
+    - the brick placed by the next press of space
+    - whether the level changed since it was last saved or loaded
+    - the message about the last thing done, if any
+    - whether the editor is waiting for the player to confirm leaving
+*/
+struct EditorState {
+    pub level: Level,
+    pub path: PathBuf,
+    pub cursor: Vec2,
+    pub brush: usize,
+    pub modified: bool,
+    pub message: String,
+    pub quitting: bool,
+}
+
+impl EditorState {
+    pub fn new(level: Level, path: &Path, message: String) -> EditorState {
+        EditorState {
+            level,
+            path: path.to_path_buf(),
+            cursor: Vec2::zero(),
+            brush: 0,
+            modified: false,
+            message,
+            quitting: false,
+        }
+    }
+
+    pub fn move_cursor(&mut self, direction: Vec2) {
+        self.cursor.x = (self.cursor.x + direction.x).clamp(0, LEVEL_WIDTH as i32 - 1);
+        self.cursor.y = (self.cursor.y + direction.y).clamp(0, LEVEL_HEIGHT as i32 - 1);
+    }
+
+    pub fn brush_char(&self) -> char {
+        levels::brick_chars().nth(self.brush).unwrap()
+    }
+
+    // Puts a brick (or nothing) in the cell under the cursor.
+    pub fn paint(&mut self, cell: char) {
+        let (x, y) = (self.cursor.x as usize, self.cursor.y as usize);
+        if self.level.get(x, y) != cell {
+            self.level.set(x, y, cell);
+            self.modified = true;
+        }
+    }
+
+    // Levels that cannot be cleared are not saved, as they could not be
+    // played either.
+    pub fn save(&mut self) {
+        if !self.level.has_breakable_bricks() {
+            self.message = "a level needs a brick that can break".to_string();
+            return;
+        }
+        self.message = match self.level.save(&self.path) {
+            Ok(()) => {
+                self.modified = false;
+                format!("saved to {}", self.path.display())
+            }
+            Err(error) => format!("could not save: {}", error),
+        };
+    }
+
+    pub fn load(&mut self) {
+        self.message = match Level::load(&self.path) {
+            Ok(level) => {
+                self.level = level;
+                self.modified = false;
+                format!("loaded {}", self.path.display())
+            }
+            Err(error) => format!("could not load: {}", error),
+        };
+    }
+
+    // Handles a key pressed in the editor. Returns false once the player
+    // is done with it.
+    pub fn handle_key(&mut self, key: Key) -> bool {
+        // Leaving with unsaved changes takes a second press
+        if let Key::Esc | Key::Q = key {
+            if !self.modified || self.quitting {
+                return false;
+            }
+            self.quitting = true;
+            self.message = "unsaved changes - press q again to quit".to_string();
+            return true;
+        }
+        self.quitting = false;
+
+        match key {
+            Key::Up => self.move_cursor(Vec2::xy(0, -1)),
+            Key::Down => self.move_cursor(Vec2::xy(0, 1)),
+            Key::Left => self.move_cursor(Vec2::xy(-1, 0)),
+            Key::Right => self.move_cursor(Vec2::xy(1, 0)),
+            Key::Tab => self.brush = (self.brush + 1) % BRUSH_KEYS.len(),
+            Key::Space | Key::Enter => self.paint(self.brush_char()),
+            Key::Backspace | Key::X => self.paint(EMPTY),
+            Key::S => self.save(),
+            Key::O => self.load(),
+            _ => {
+                if let Some(brush) = BRUSH_KEYS.iter().position(|brush_key| *brush_key == key) {
+                    self.brush = brush;
+                }
+            }
+        }
+        true
+    }
+
+    // The size of everything the editor draws: the title, the grid and its
+    // border, the bricks to choose from, the help and the last message.
+    pub fn size() -> Vec2 {
+        let grid_size = Vec2::xy(LEVEL_WIDTH as i32 * CELL.x, LEVEL_HEIGHT as i32 * CELL.y);
+        Vec2::xy(grid_size.x + 2, GRID_ORIGIN.y + grid_size.y + 8)
+    }
+
+    // Draws the editor with its top left corner at the origin of the pencil.
+    pub fn draw(&self, pencil: &mut Pencil) {
+        let title = format!("{}{}", self.path.display(), if self.modified { " *" } else { "" });
+        pencil.set_foreground(Color::White);
+        pencil.draw_text(&title, Vec2::zero());
+
+        // Everything else is drawn from the top left corner of the grid
+        pencil.move_origin(GRID_ORIGIN);
+        let grid_size = Vec2::xy(LEVEL_WIDTH as i32 * CELL.x, LEVEL_HEIGHT as i32 * CELL.y);
+        pencil.set_foreground(Color::DarkGrey);
+        pencil.draw_rect(&RectCharset::simple_lines(), Vec2::xy(-1, -1), grid_size + Vec2::xy(2, 2));
+
+        // Draw the bricks as they look in the game, where every column of
+        // the level is a row of bricks
+        for y in 0..LEVEL_HEIGHT {
+            for x in 0..LEVEL_WIDTH {
+                let position = Vec2::xy(x as i32 * CELL.x, y as i32 * CELL.y);
+                match levels::brick(self.level.get(x, y)) {
+                    Some((kind, paint)) => {
+                        draw_brick(pencil, &BrickState::new(position, kind, paint), x, CELL)
+                    }
+                    None => {
+                        pencil.set_foreground(Color::DarkGrey);
+                        pencil.draw_char(EMPTY, position + CELL / 2);
+                    }
+                }
+            }
+        }
+
+        // Draw the cursor around its cell
+        pencil.set_foreground(Color::Yellow);
+        pencil.set_style(Style::Bold);
+        let cursor = Vec2::xy(self.cursor.x * CELL.x, self.cursor.y * CELL.y);
+        pencil.draw_rect(&RectCharset::double_lines(), cursor, CELL);
+        pencil.set_style(Style::Plain);
+
+        // Draw the bricks to choose from, in the color of the cursor's column
+        let mut x = 0;
+        let y = grid_size.y + 1;
+        for (index, cell) in levels::brick_chars().enumerate() {
+            let (kind, paint) = levels::brick(cell).unwrap();
+            let brick = BrickState::new(Vec2::xy(x + 2, y), kind, paint);
+            let key = (index + 1) % BRUSH_KEYS.len();
+            pencil.set_foreground(if index == self.brush { Color::Yellow } else { Color::Grey });
+            let marker = if index == self.brush { ">" } else { " " };
+            pencil.draw_text(&format!("{}{}", marker, key), Vec2::xy(x, y));
+            pencil.set_foreground(brick.color(self.cursor.x as usize));
+            pencil.draw_char(cell, brick.position);
+            x += 4;
+        }
+
+        pencil.set_foreground(Color::DarkGrey);
+        for (index, line) in HELP.iter().enumerate() {
+            pencil.draw_text(line, Vec2::xy(0, y + 2 + index as i32));
+        }
+
+        pencil.set_foreground(Color::Yellow);
+        pencil.draw_text(&self.message, Vec2::xy(0, y + 6));
+        pencil.move_origin(-GRID_ORIGIN);
+    }
+}
+
+// Edits the level in the given file until the player quits, starting from
+// an empty level if there is no such file yet.
+pub fn edit_level(path: &Path) -> io::Result<()> {
+    let (level, message) = match Level::load(path) {
+        Ok(level) => (level, format!("loaded {}", path.display())),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            (Level::empty(), "new level".to_string())
+        }
+        Err(error) => return Err(error),
+    };
+    let mut state = EditorState::new(level, path, message);
+
+    let mut app = App::default();
+    app.run(|app_state: &mut State, window: &mut Window| {
+        for key_event in app_state.keyboard().last_key_events().iter().copied() {
+            if let KeyEvent::Pressed(key) = key_event {
+                if !state.handle_key(key) {
+                    app_state.stop();
+                }
+            }
+        }
+
+        let win_size = window.size();
+        let mut pencil = Pencil::new(window.canvas_mut());
+        pencil.set_origin((win_size - EditorState::size()) / 2);
+        state.draw(&mut pencil);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ruscii::terminal::{Canvas, VisualElement};
+
+    use super::*;
+    use crate::games::breakout::BrickKind;
+
+    fn editor(path: &Path) -> EditorState {
+        EditorState::new(Level::empty(), path, String::new())
+    }
+
+    #[test]
+    fn cursor_stays_on_the_grid() {
+        let mut state = editor(Path::new("level.txt"));
+        state.handle_key(Key::Up);
+        state.handle_key(Key::Left);
+        assert_eq!(state.cursor, Vec2::zero());
+        for _ in 0..20 {
+            state.handle_key(Key::Right);
+            state.handle_key(Key::Down);
+        }
+        assert_eq!(state.cursor, Vec2::xy(LEVEL_WIDTH as i32 - 1, LEVEL_HEIGHT as i32 - 1));
+    }
+
+    #[test]
+    fn paint_and_erase() {
+        let mut state = editor(Path::new("level.txt"));
+        state.handle_key(Key::Num3);
+        state.handle_key(Key::Right);
+        state.handle_key(Key::Space);
+        assert_eq!(state.level.get(1, 0), levels::brick_chars().nth(2).unwrap());
+        assert!(state.modified);
+
+        state.handle_key(Key::X);
+        assert_eq!(state.level.get(1, 0), EMPTY);
+    }
+
+    #[test]
+    fn quitting_with_unsaved_changes_takes_a_second_press() {
+        let mut state = editor(Path::new("level.txt"));
+        assert!(!state.handle_key(Key::Q));
+
+        state.handle_key(Key::Space);
+        assert!(state.handle_key(Key::Q));
+        assert!(state.message.contains("unsaved changes"));
+        // Any other key cancels leaving
+        state.handle_key(Key::Down);
+        assert!(state.handle_key(Key::Esc));
+        assert!(!state.handle_key(Key::Q));
+    }
+
+    #[test]
+    fn levels_without_breakable_bricks_are_not_saved() {
+        let path = std::env::temp_dir().join(format!("rusticade-editor-{}.txt", std::process::id()));
+        let mut state = editor(&path);
+        state.brush = levels::brick_chars()
+            .position(|cell| levels::brick(cell).is_some_and(|(kind, _)| kind == BrickKind::Steel))
+            .unwrap();
+        state.handle_key(Key::Space);
+        state.handle_key(Key::S);
+        assert_eq!(state.message, "a level needs a brick that can break");
+        assert!(state.modified);
+        assert!(!path.exists());
+
+        state.handle_key(Key::Num1);
+        state.handle_key(Key::Right);
+        state.handle_key(Key::Space);
+        state.handle_key(Key::S);
+        assert!(!state.modified);
+        assert_eq!(Level::load(&path).unwrap(), state.level);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn everything_is_drawn_within_the_editor_size() {
+        let size = EditorState::size();
+        let mut canvas = Canvas::new(size, &VisualElement::default());
+        let mut state = EditorState::new(Level::empty(), Path::new("level.txt"), "new level".to_string());
+        state.handle_key(Key::Space);
+        state.draw(&mut Pencil::new(&mut canvas));
+
+        let row = |y| -> String {
+            (0..size.x).map(|x| canvas.elem(Vec2::xy(x, y)).unwrap().value).collect()
+        };
+        assert!(row(0).starts_with("level.txt *"));
+        assert!(row(1).starts_with('┌') && row(1).trim_end().ends_with('┐'));
+        assert_eq!(row(size.y - 1).trim(), "new level");
+    }
+}
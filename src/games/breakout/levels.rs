@@ -0,0 +1,229 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ruscii::terminal::Color;
+
+use crate::paths::invalid_data;
+
+use super::BrickKind;
+
+// The most bricks a level can have across and down
+pub const LEVEL_WIDTH: usize = 10;
+pub const LEVEL_HEIGHT: usize = 10;
+
+// The character of a cell without a brick
+pub const EMPTY: char = '.';
+
+// Every character that stands for a brick, with its kind and its color
+// (None for the color of its column)
+const BRICKS: [(char, BrickKind, Option<Color>); 10] = [
+    ('=', BrickKind::Normal, None),
+    ('r', BrickKind::Normal, Some(Color::Red)),
+    ('o', BrickKind::Normal, Some(Color::Xterm(166))),
+    ('y', BrickKind::Normal, Some(Color::Yellow)),
+    ('g', BrickKind::Normal, Some(Color::Green)),
+    ('b', BrickKind::Normal, Some(Color::Blue)),
+    ('c', BrickKind::Normal, Some(Color::Cyan)),
+    ('H', BrickKind::Hard, None),
+    ('S', BrickKind::Steel, None),
+    ('X', BrickKind::Explosive, None),
+];
+
+// The levels played in order, starting over after the last one
+const BUILT_IN: [&str; 4] = [
+    "\
+HHHHHHHH
+=X======
+=======X
+====X===
+=X======
+=======X
+=S==S==S
+=X======
+=======X
+====X===
+",
+    "\
+rrrrrrrrrr
+oooooooooo
+yyyyyyyyyy
+gggggggggg
+bbbbbbbbbb
+cccccccccc
+",
+    "\
+....HH....
+...=XX=...
+..======..
+.==X==X==.
+==========
+S.S.S.S.S.
+",
+    "\
+SSSS..SSSS
+S=HHHHHH=S
+S=rrXXrr=S
+S=oooooo=S
+S=yyyyyy=S
+S........S
+S=gggggg=S
+SSS....SSS
+",
+];
+
+// The kind and color of the brick a character stands for.
+pub fn brick(cell: char) -> Option<(BrickKind, Option<Color>)> {
+    BRICKS.iter().find(|(c, _, _)| *c == cell).map(|(_, kind, color)| (*kind, *color))
+}
+
+// Every character that stands for a brick, in order.
+pub fn brick_chars() -> impl Iterator<Item = char> {
+    BRICKS.iter().map(|(c, _, _)| *c)
+}
+
+/*
+    Level defines the layout of the bricks at the start of a level.
+    - one character per cell of the grid, row by row
+
+    On disk it is a text file with one line per row of bricks, from the top,
+    and one character per brick, from the left. `.` or a space is a cell
+    without a brick; `=` is a brick in the color of its column, `r`, `o`, `y`,
+    `g`, `b` and `c` are bricks of a given color, `H` is a hard brick, `S` a
+    steel one and `X` an explosive one. Lines starting with `#` are ignored.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Level {
+    cells: Vec<Vec<char>>,
+}
+
+impl Level {
+    pub fn empty() -> Level {
+        Level {
+            cells: vec![vec![EMPTY; LEVEL_WIDTH]; LEVEL_HEIGHT],
+        }
+    }
+
+    pub fn built_in() -> Vec<Level> {
+        BUILT_IN.iter().map(|text| Level::parse(text).unwrap()).collect()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Level> {
+        Level::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Level> {
+        let mut level = Level::empty();
+        let rows = text.lines().filter(|line| !line.starts_with('#'));
+        for (y, line) in rows.enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if y >= LEVEL_HEIGHT {
+                return Err(invalid_data(format!("more than {} rows of bricks", LEVEL_HEIGHT)));
+            }
+            if line.chars().count() > LEVEL_WIDTH {
+                return Err(invalid_data(format!("more than {} bricks in row {}", LEVEL_WIDTH, y + 1)));
+            }
+            for (x, cell) in line.chars().enumerate() {
+                let cell = if cell == ' ' { EMPTY } else { cell };
+                if cell != EMPTY && brick(cell).is_none() {
+                    return Err(invalid_data(format!("unknown brick '{}' in row {}", cell, y + 1)));
+                }
+                level.cells[y][x] = cell;
+            }
+        }
+
+        if !level.has_breakable_bricks() {
+            return Err(invalid_data("the level has no bricks to break".to_string()));
+        }
+        Ok(level)
+    }
+
+    // The level as written to a file, without the empty cells at the end of
+    // each row or the empty rows at the bottom.
+    pub fn to_text(&self) -> String {
+        let mut rows: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end_matches(EMPTY).to_string())
+            .collect();
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        rows.iter().map(|row| format!("{}\n", row)).collect()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> char {
+        self.cells[y][x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: char) {
+        self.cells[y][x] = cell;
+    }
+
+    // Every brick of the level with its column, its row, its kind and its color.
+    pub fn bricks(&self) -> impl Iterator<Item = (usize, usize, BrickKind, Option<Color>)> + '_ {
+        self.cells.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(x, cell)| brick(*cell).map(|(kind, color)| (x, y, kind, color)))
+        })
+    }
+
+    // Whether the level can be cleared: steel bricks never break.
+    pub fn has_breakable_bricks(&self) -> bool {
+        self.bricks().any(|(_, _, kind, _)| kind != BrickKind::Steel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> String {
+        Level::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parse() {
+        let level = Level::parse("# a comment\nH S\n\n.=rX\n").unwrap();
+        assert_eq!(level.get(0, 0), 'H');
+        assert_eq!(level.get(1, 0), EMPTY);
+        assert_eq!(level.get(2, 0), 'S');
+        // An empty line is a row without bricks
+        assert_eq!(level.get(3, 2), 'X');
+        let bricks: Vec<(usize, usize)> = level.bricks().map(|(x, y, _, _)| (x, y)).collect();
+        assert_eq!(bricks, [(0, 0), (2, 0), (1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse_error(&"=\n".repeat(11)), "more than 10 rows of bricks");
+        assert_eq!(parse_error("===========\n"), "more than 10 bricks in row 1");
+        assert_eq!(parse_error("==\n=?\n"), "unknown brick '?' in row 2");
+        assert_eq!(parse_error("SSS\n...\n"), "the level has no bricks to break");
+        assert_eq!(parse_error(""), "the level has no bricks to break");
+        assert_eq!(Level::parse("SSS\n").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        for level in Level::built_in() {
+            assert_eq!(Level::parse(&level.to_text()).unwrap(), level);
+        }
+
+        // Empty cells at the end of the rows and empty rows at the bottom
+        // are left out
+        let mut level = Level::empty();
+        level.set(1, 0, '=');
+        level.set(0, 2, 'H');
+        assert_eq!(level.to_text(), ".=\n\nH\n");
+        assert_eq!(Level::parse(&level.to_text()).unwrap(), level);
+    }
+}
@@ -0,0 +1,123 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use ruscii::terminal::Color;
+
+use crate::physics::Vec2f;
+
+// How likely a broken brick is to drop a capsule
+const DROP_CHANCE: f64 = 0.15;
+// How fast capsules fall, in cells per tick
+const CAPSULE_SPEED: f32 = 0.25;
+// How long timed power-ups last, in ticks (15 seconds)
+const EFFECT_TICKS: u32 = 450;
+
+// The power-ups a capsule can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUp {
+    // Makes the bouncer half as wide again
+    Wide,
+    // The bouncer catches the ball until it is fired again
+    Sticky,
+    // Splits every ball in three
+    Multiball,
+    // The bouncer fires lasers that break bricks
+    Laser,
+    // Slows every ball down
+    Slow,
+    // One more ball can be missed
+    ExtraLife,
+}
+
+const POWER_UPS: [PowerUp; 6] = [
+    PowerUp::Wide,
+    PowerUp::Sticky,
+    PowerUp::Multiball,
+    PowerUp::Laser,
+    PowerUp::Slow,
+    PowerUp::ExtraLife,
+];
+
+impl PowerUp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUp::Wide => "wide",
+            PowerUp::Sticky => "sticky",
+            PowerUp::Multiball => "multiball",
+            PowerUp::Laser => "laser",
+            PowerUp::Slow => "slow",
+            PowerUp::ExtraLife => "extra life",
+        }
+    }
+
+    // The letter drawn on the capsule.
+    pub fn letter(&self) -> char {
+        match self {
+            PowerUp::Wide => 'W',
+            PowerUp::Sticky => 'S',
+            PowerUp::Multiball => 'M',
+            PowerUp::Laser => 'L',
+            PowerUp::Slow => 'D',
+            PowerUp::ExtraLife => '+',
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            PowerUp::Wide => Color::Blue,
+            PowerUp::Sticky => Color::Green,
+            PowerUp::Multiball => Color::Cyan,
+            PowerUp::Laser => Color::Red,
+            PowerUp::Slow => Color::Yellow,
+            PowerUp::ExtraLife => Color::Magenta,
+        }
+    }
+
+    // How many ticks the power-up lasts, or None if it takes effect once.
+    pub fn duration(&self) -> Option<u32> {
+        match self {
+            PowerUp::Multiball | PowerUp::ExtraLife => None,
+            _ => Some(EFFECT_TICKS),
+        }
+    }
+
+    // Picks the power-up dropped by a broken brick, if any.
+    pub fn random_drop(rng: &mut StdRng) -> Option<PowerUp> {
+        if rng.gen_bool(DROP_CHANCE) {
+            Some(POWER_UPS[rng.gen_range(0..POWER_UPS.len())])
+        } else {
+            None
+        }
+    }
+}
+
+/*
+    Capsule defines a power-up falling towards the bouncer.
+    - its current position (between cells)
+    - the power-up it holds
+*/
+#[derive(Clone, Debug)]
+pub struct Capsule {
+    pub position: Vec2f,
+    pub power_up: PowerUp,
+}
+
+impl Capsule {
+    pub fn new(position: Vec2f, power_up: PowerUp) -> Capsule {
+        Capsule { position, power_up }
+    }
+
+    pub fn fall(&mut self) {
+        self.position.y += CAPSULE_SPEED;
+    }
+}
+
+/*
+    Effect defines a power-up the bouncer is under.
+    - the power-up
+    - how many ticks it lasts for
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Effect {
+    pub power_up: PowerUp,
+    pub ticks_left: u32,
+}
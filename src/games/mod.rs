@@ -0,0 +1,40 @@
+pub mod breakout;
+pub mod pong;
+pub mod spaceinvaders;
+
+use crate::game::{self, Game};
+use crate::options::Options;
+use crate::replay::Replay;
+
+/*
+    GameEntry describes a game that can be picked from the launcher.
+    - the name shown in the menu
+    - the function that plays the game until it ends
+    - the function that plays a replay of the game
+*/
+pub struct GameEntry {
+    pub name: &'static str,
+    pub run: fn(&Options),
+    pub replay: fn(&Replay),
+}
+
+impl GameEntry {
+    const fn of<G: Game>() -> GameEntry {
+        GameEntry {
+            name: G::NAME,
+            run: game::run::<G>,
+            replay: game::replay::<G>,
+        }
+    }
+}
+
+// The playable games, in the same order as GAMES.md
+pub static GAMES: [GameEntry; 3] = [
+    GameEntry::of::<breakout::GameState>(),
+    GameEntry::of::<spaceinvaders::GameState>(),
+    GameEntry::of::<pong::GameState>(),
+];
+
+pub fn find_game(name: &str) -> Option<&'static GameEntry> {
+    GAMES.iter().find(|entry| entry.name == name)
+}
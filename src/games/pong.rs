@@ -1,53 +1,411 @@
-use ruscii::app::{App, State};
+pub mod cpu;
+
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_4, FRAC_PI_8};
+
 use ruscii::drawing::{Pencil, RectCharset};
-use ruscii::gui::FPSCounter;
-use ruscii::keyboard::{Key, KeyEvent};
+use ruscii::keyboard::Key;
 use ruscii::spatial::Vec2;
-use ruscii::terminal::{Color, Style, Window};
+use ruscii::terminal::{Color, Style};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::bindings::{Action, ActionEvent};
+use crate::collision::{self, Rect};
+use crate::game::Game;
+use crate::physics::Vec2f;
 
-use rand::{self, prelude::*};
+use self::cpu::{Cpu, Difficulty};
 
 const PAD_HEIGHT: i32 = 3;
+// The court takes 4/5 of the window and must fit both paddles with room to move
+const MIN_WINDOW: Vec2 = Vec2 { x: 40, y: 15 };
+// Speeds of the ball, in cells per tick
+const BALL_SPEED: f32 = 1.4;
+const MAX_BALL_SPEED: f32 = 2.5;
+// How much faster the ball gets every time it is returned during a rally
+const RALLY_SPEED_RAMP: f32 = 1.04;
+// The angle from horizontal the ball leaves at when it hits the end of a paddle
+const MAX_BOUNCE_ANGLE: f32 = FRAC_PI_3;
+// How much a moving paddle turns the ball, in radians
+const SPIN: f32 = 0.25;
+
+// How long the ball waits in the middle before it is served, in ticks
+const SERVE_DELAY: u32 = 15;
+
+// The rows of the setup screen: both paddles, the difficulty, the points to
+// win and start
+const SETUP_ROWS: usize = 5;
+// The points to win a match that can be chosen on the setup screen
+const POINTS_TO_WIN: [usize; 4] = [5, 11, 15, 21];
+
+// One of the two sides of the court.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        }
+    }
+
+    pub fn other(&self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/*
+    MatchRules defines how a match is won.
+    - the points needed to win
+    - how many points ahead of the other player the winner must be
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct MatchRules {
+    pub points_to_win: usize,
+    pub win_by: usize,
+}
+
+impl Default for MatchRules {
+    fn default() -> MatchRules {
+        MatchRules {
+            points_to_win: 11,
+            win_by: 2,
+        }
+    }
+}
+
+impl MatchRules {
+    // The side that won the match with the given scores, if any.
+    pub fn winner(&self, left: usize, right: usize) -> Option<Side> {
+        if left >= self.points_to_win && left >= right + self.win_by {
+            Some(Side::Left)
+        } else if right >= self.points_to_win && right >= left + self.win_by {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
 
-struct PlayerState {
+    // Whether both players are one point away from the target, so that the
+    // match goes on until one of them is two points ahead.
+    pub fn is_deuce(&self, left: usize, right: usize) -> bool {
+        left + 1 >= self.points_to_win && right + 1 >= self.points_to_win
+    }
+}
+
+/*
+    PlayerState defines the state of a paddle.
+    - its position (the middle of the paddle)
+    - which direction it is moving this tick: -1, 0 or 1
+    - the points won
+    - the computer moving it (None if a player does)
+*/
+pub struct PlayerState {
     pub position: Vec2,
-    // -1, 0, 1
     pub direction: i32,
     pub score: usize,
+    pub cpu: Option<Cpu>,
+}
+
+impl PlayerState {
+    pub fn new(position: Vec2) -> PlayerState {
+        PlayerState {
+            position,
+            direction: 0,
+            score: 0,
+            cpu: None,
+        }
+    }
+}
+
+/*
+    Setup defines the choices made on the screen shown before the match.
+    - the highlighted row
+    - whether the computer plays the left paddle and the right paddle
+    - how well the computer plays
+    - the points needed to win the match
+*/
+pub struct Setup {
+    pub selected: usize,
+    pub left_cpu: bool,
+    pub right_cpu: bool,
+    pub difficulty: Difficulty,
+    pub points_to_win: usize,
+}
+
+impl Setup {
+    // Goes to the next choice of points to win, back to the first after the last.
+    fn next_points_to_win(&mut self) {
+        let index = POINTS_TO_WIN.iter().position(|points| *points == self.points_to_win);
+        self.points_to_win = POINTS_TO_WIN[index.map_or(0, |index| (index + 1) % POINTS_TO_WIN.len())];
+    }
+
+    fn lines(&self) -> [String; SETUP_ROWS] {
+        let controller = |cpu: bool| if cpu { "computer" } else { "player" };
+        [
+            format!("left paddle:  {}", controller(self.left_cpu)),
+            format!("right paddle: {}", controller(self.right_cpu)),
+            format!("difficulty:   {}", self.difficulty.name()),
+            format!("first to:     {}", self.points_to_win),
+            "start".to_string(),
+        ]
+    }
 }
 
-struct GameState {
+/*
+    GameState defines the state of a match of Pong.
+    - the size of the court
+    - both paddles
+    - the position and the speed of the ball
+    - how many times the ball was returned since it was served
+    - how the match is won
+    - the side the next ball is served towards, and the ticks left before it is
+    - the choices on the setup screen, until the match starts
+*/
+pub struct GameState {
     pub dimension: Vec2,
     pub left_player: PlayerState,
     pub right_player: PlayerState,
-    pub ball_position: Vec2,
-    pub ball_speed: Vec2,
+    pub ball_position: Vec2f,
+    pub ball_speed: Vec2f,
+    pub rally: u32,
+    pub rules: MatchRules,
+    pub serve_toward: Side,
+    pub serve_delay: u32,
+    pub setup: Option<Setup>,
+    pub rng: StdRng,
 }
 
 impl GameState {
-    pub fn new(dim: Vec2) -> Self {
+    // Starts the match with the choices made on the setup screen.
+    fn start(&mut self) {
+        if let Some(setup) = self.setup.take() {
+            self.left_player.cpu = setup.left_cpu.then(|| Cpu::new(setup.difficulty));
+            self.right_player.cpu = setup.right_cpu.then(|| Cpu::new(setup.difficulty));
+            self.rules.points_to_win = setup.points_to_win;
+        }
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        self.rules.winner(self.left_player.score, self.right_player.score)
+    }
+
+    // Gives the point to `side` and puts the ball back in the middle, to be
+    // served towards the player who lost the point after a short delay.
+    fn win_point(&mut self, side: Side) {
+        match side {
+            Side::Left => self.left_player.score += 1,
+            Side::Right => self.right_player.score += 1,
+        }
+        self.ball_position = Vec2f::from(self.dimension / 2);
+        self.ball_speed = Vec2f::zero();
+        self.rally = 0;
+        self.serve_toward = side.other();
+        self.serve_delay = SERVE_DELAY;
+    }
+
+    // Sends the ball back from the paddle on `side`, which it hit at the
+    // height `hit_y`. The further from the middle of the paddle it hit, the
+    // steeper it leaves, and a moving paddle puts some spin on it. The ball
+    // gets a bit faster with every return of a rally.
+    fn return_ball(&mut self, side: Side, hit_y: f32) {
+        let player = match side {
+            Side::Left => &self.left_player,
+            Side::Right => &self.right_player,
+        };
+        // The paddle covers the cells from PAD_HEIGHT above its position to
+        // PAD_HEIGHT - 1 below it
+        let center = player.position.y as f32 - 0.5;
+        let offset = ((hit_y - center) / PAD_HEIGHT as f32).clamp(-1.0, 1.0);
+        let angle = (offset * MAX_BOUNCE_ANGLE + player.direction as f32 * SPIN)
+            .clamp(-MAX_BOUNCE_ANGLE, MAX_BOUNCE_ANGLE);
+
+        self.rally += 1;
+        let speed = (BALL_SPEED * RALLY_SPEED_RAMP.powi(self.rally as i32)).min(MAX_BALL_SPEED);
+        let away = if side == Side::Left { 1.0 } else { -1.0 };
+        self.ball_speed = Vec2f::xy(angle.cos() * away, angle.sin()) * speed;
+    }
+
+    // What is shown next to the scores.
+    fn status(&self) -> String {
+        let (left, right) = (self.left_player.score, self.right_player.score);
+        if let Some(winner) = self.winner() {
+            format!("{} player wins!", winner.name())
+        } else if !self.rules.is_deuce(left, right) {
+            format!("first to {}", self.rules.points_to_win)
+        } else if left == right {
+            "deuce".to_string()
+        } else if left > right {
+            "advantage left".to_string()
+        } else {
+            "advantage right".to_string()
+        }
+    }
+
+    // Lets the computer decide where to move the paddles it plays.
+    fn move_cpus(&mut self) {
+        if let Some(cpu) = &mut self.left_player.cpu {
+            self.left_player.direction = cpu.direction(
+                self.left_player.position,
+                self.ball_position,
+                self.ball_speed,
+                self.dimension,
+                &mut self.rng,
+            );
+        }
+        if let Some(cpu) = &mut self.right_player.cpu {
+            self.right_player.direction = cpu.direction(
+                self.right_player.position,
+                self.ball_position,
+                self.ball_speed,
+                self.dimension,
+                &mut self.rng,
+            );
+        }
+    }
+}
+
+// Serves the ball towards `side`, between 22.5 and 45 degrees from horizontal.
+pub fn random_ball_direction(rng: &mut StdRng, side: Side) -> Vec2f {
+    let neg_x = side == Side::Left;
+    let neg_y: bool = rng.gen();
+    let angle = rng.gen_range(FRAC_PI_8..FRAC_PI_4);
+    let direction = Vec2f::xy(angle.cos(), angle.sin()) * BALL_SPEED;
+    Vec2f::xy(
+        if neg_x { -direction.x } else { direction.x },
+        if neg_y { -direction.y } else { direction.y },
+    )
+}
+
+impl Game for GameState {
+    const NAME: &'static str = "Pong";
+    const TICKS_PER_SECOND: u32 = 15;
+    const MIN_WINDOW: Vec2 = MIN_WINDOW;
+    const BINDINGS: &'static [(Action, &'static [Key])] = &[
+        (Action::MoveUp, &[Key::W]),
+        (Action::MoveDown, &[Key::S]),
+        (Action::Player2Up, &[Key::Up]),
+        (Action::Player2Down, &[Key::Down]),
+        (Action::Fire, &[Key::Space, Key::Enter]),
+    ];
+    // The court is drawn with its own border
+    const BORDER: bool = false;
+
+    fn new(win_size: Vec2, seed: u64) -> Self {
+        let dim = (win_size * 4) / 5;
+        let mut rng = StdRng::seed_from_u64(seed);
         Self {
             dimension: dim,
-            left_player: PlayerState {
-                position: Vec2::xy(1, dim.y / 2),
-                direction: 0,
-                score: 0,
-            },
-            right_player: PlayerState {
-                position: Vec2::xy(dim.x - 3, dim.y / 2),
-                direction: 0,
-                score: 0,
+            left_player: PlayerState::new(Vec2::xy(1, dim.y / 2)),
+            right_player: PlayerState::new(Vec2::xy(dim.x - 3, dim.y / 2)),
+            ball_position: Vec2f::from(dim / 2),
+            ball_speed: Vec2f::zero(),
+            rally: 0,
+            rules: MatchRules::default(),
+            serve_toward: if rng.gen() { Side::Left } else { Side::Right },
+            serve_delay: SERVE_DELAY,
+            setup: Some(Setup {
+                selected: SETUP_ROWS - 1,
+                left_cpu: false,
+                right_cpu: true,
+                difficulty: Difficulty::Normal,
+                points_to_win: MatchRules::default().points_to_win,
+            }),
+            rng,
+        }
+    }
+
+    fn dimension(&self) -> Vec2 {
+        self.dimension
+    }
+
+    // The court keeps taking 4/5 of the window, and the paddles and the ball
+    // keep their place relative to its size.
+    fn resize(&mut self, win_size: Vec2) {
+        let old = self.dimension;
+        let dim = (win_size * 4) / 5;
+        let scale_y = |y: i32| (y * dim.y / old.y.max(1)).clamp(PAD_HEIGHT, dim.y - PAD_HEIGHT);
+
+        self.left_player.position = Vec2::xy(1, scale_y(self.left_player.position.y));
+        self.right_player.position = Vec2::xy(dim.x - 3, scale_y(self.right_player.position.y));
+        self.ball_position = Vec2f::xy(
+            self.ball_position.x * dim.x as f32 / old.x.max(1) as f32,
+            self.ball_position.y * dim.y as f32 / old.y.max(1) as f32,
+        );
+        self.dimension = dim;
+    }
+
+    // Moves through the setup screen until the match starts.
+    fn handle_action_event(&mut self, event: &ActionEvent) {
+        let setup = match &mut self.setup {
+            Some(setup) => setup,
+            None => return,
+        };
+        match event {
+            ActionEvent::Pressed(Action::MoveUp | Action::Player2Up) => {
+                setup.selected = (setup.selected + SETUP_ROWS - 1) % SETUP_ROWS
+            }
+            ActionEvent::Pressed(Action::MoveDown | Action::Player2Down) => {
+                setup.selected = (setup.selected + 1) % SETUP_ROWS
+            }
+            ActionEvent::Pressed(Action::Fire) => match setup.selected {
+                0 => setup.left_cpu = !setup.left_cpu,
+                1 => setup.right_cpu = !setup.right_cpu,
+                2 => setup.difficulty = setup.difficulty.next(),
+                3 => setup.next_points_to_win(),
+                _ => self.start(),
             },
-            ball_position: dim / 2,
-            ball_speed: Self::random_ball_direction(),
+            _ => (),
         }
     }
 
+    fn handle_actions_down(&mut self, actions: &[Action]) {
+        if self.setup.is_some() {
+            return;
+        }
+
+        // A player alone against the computer can use either set of keys
+        let alone = self.left_player.cpu.is_some() != self.right_player.cpu.is_some();
+        for action in actions {
+            let direction = match action {
+                Action::MoveUp | Action::Player2Up => -1,
+                Action::MoveDown | Action::Player2Down => 1,
+                _ => continue,
+            };
+            let on_the_left = if alone {
+                self.left_player.cpu.is_none()
+            } else {
+                matches!(action, Action::MoveUp | Action::MoveDown)
+            };
+            let player = if on_the_left { &mut self.left_player } else { &mut self.right_player };
+            if player.cpu.is_none() {
+                player.direction = direction;
+            }
+        }
+    }
 
+    fn update(&mut self) {
+        if self.setup.is_some() || self.winner().is_some() {
+            return;
+        }
 
-    pub fn update(&mut self) {
-        self.ball_position += self.ball_speed;
+        // Serve the ball once it has waited long enough in the middle
+        if self.serve_delay > 0 {
+            self.serve_delay -= 1;
+            if self.serve_delay == 0 {
+                self.ball_speed = random_ball_direction(&mut self.rng, self.serve_toward);
+            }
+        }
 
+        self.move_cpus();
         if self.left_player.position.y + PAD_HEIGHT < self.dimension.y
             && self.left_player.direction > 0
             || self.left_player.position.y - PAD_HEIGHT > 0 && self.left_player.direction < 0
@@ -62,116 +420,243 @@ impl GameState {
             self.right_player.position.y += self.right_player.direction;
         }
 
-        if self.ball_position.y >= self.dimension.y - 1 && self.ball_speed.y > 0 {
-            self.ball_position.y = self.dimension.y - 1;
-            self.ball_speed.y = -self.ball_speed.y;
+        // Move the ball, returning it from the first paddle in its way and
+        // carrying on with the rest of its motion
+        let pad_size = Vec2::xy(2, PAD_HEIGHT * 2);
+        let hit = [(Side::Left, &self.left_player), (Side::Right, &self.right_player)]
+            .iter()
+            .filter_map(|(side, player)| {
+                let pad = Rect::from_cells(player.position - Vec2::y(PAD_HEIGHT), pad_size);
+                collision::sweep_point(self.ball_position, self.ball_speed, &pad).map(|hit| (*side, hit))
+            })
+            .min_by(|(_, a), (_, b)| a.time.total_cmp(&b.time));
+        match hit {
+            Some((side, hit)) => {
+                // Only the face of a paddle returns the ball, its ends just
+                // bounce it off
+                if hit.side.is_vertical() {
+                    self.return_ball(side, hit.position.y);
+                } else {
+                    self.ball_speed.y = -self.ball_speed.y;
+                }
+                self.ball_position = hit.position + self.ball_speed * (1.0 - hit.time);
+            }
+            None => self.ball_position += self.ball_speed,
         }
 
-        if self.ball_position.y <= 0 && self.ball_speed.y < 0 {
-            self.ball_position.y = 0;
+        let bottom = (self.dimension.y - 1) as f32;
+        if self.ball_position.y >= bottom && self.ball_speed.y > 0.0 {
+            self.ball_position.y = bottom;
             self.ball_speed.y = -self.ball_speed.y;
         }
 
-        if self.ball_position.x <= self.left_player.position.x + 1
-            && self.ball_position.y <= self.left_player.position.y + PAD_HEIGHT
-            && self.ball_position.y >= self.left_player.position.y - PAD_HEIGHT
-        {
-            self.ball_position.x = self.left_player.position.x + 1;
-            self.ball_speed.x = -self.ball_speed.x;
-        }
-
-        if self.ball_position.x >= self.right_player.position.x
-            && self.ball_position.y <= self.right_player.position.y + PAD_HEIGHT
-            && self.ball_position.y >= self.right_player.position.y - PAD_HEIGHT
-        {
-            self.ball_position.x = self.right_player.position.x;
-            self.ball_speed.x = -self.ball_speed.x;
+        if self.ball_position.y <= 0.0 && self.ball_speed.y < 0.0 {
+            self.ball_position.y = 0.0;
+            self.ball_speed.y = -self.ball_speed.y;
         }
 
-        if self.ball_position.x <= 0 {
-            self.right_player.score += 1;
-            self.ball_position = self.dimension / 2;
-            self.ball_speed = Self::random_ball_direction();
+        if self.ball_position.x <= 0.0 {
+            self.win_point(Side::Right);
         }
 
-        if self.ball_position.x >= self.dimension.x - 1 {
-            self.left_player.score += 1;
-            self.ball_position = self.dimension / 2;
-            self.ball_speed = Self::random_ball_direction();
+        if self.ball_position.x >= (self.dimension.x - 1) as f32 {
+            self.win_point(Side::Left);
         }
 
         self.left_player.direction = 0;
         self.right_player.direction = 0;
     }
-}
 
-fn main() {
-    let mut fps_counter = FPSCounter::default();
-    let mut app = App::default();
-    let win_size = app.window().size();
-    let mut state = GameState::new((win_size * 4) / 5);
-
-    app.run(|app_state: &mut State, window: &mut Window| {
-        for key_event in app_state.keyboard().last_key_events() {
-            match key_event {
-                KeyEvent::Pressed(Key::Esc) => app_state.stop(),
-                KeyEvent::Pressed(Key::Q) => app_state.stop(),
-                _ => (),
+    fn render(&self, pencil: &mut Pencil) {
+        if let Some(setup) = &self.setup {
+            pencil.draw_rect(&RectCharset::simple_round_lines(), Vec2::zero(), self.dimension);
+            let top = (self.dimension.y - SETUP_ROWS as i32) / 2;
+            pencil.set_style(Style::Bold);
+            pencil.draw_text("P O N G", Vec2::xy((self.dimension.x - 7) / 2, top - 2));
+            pencil.set_style(Style::Plain);
+            for (index, line) in setup.lines().iter().enumerate() {
+                let position = Vec2::xy((self.dimension.x - 24) / 2, top + index as i32);
+                if index == setup.selected {
+                    pencil.set_foreground(Color::Yellow);
+                    pencil.draw_text(&format!("> {}", line), position - Vec2::x(2));
+                } else {
+                    pencil.set_foreground(Color::White);
+                    pencil.draw_text(line, position);
+                }
             }
-        }
-
-        for key_down in app_state.keyboard().get_keys_down() {
-            match key_down {
-                Key::W => state.left_player.direction = -1,
-                Key::S => state.left_player.direction = 1,
-                Key::Up => state.right_player.direction = -1,
-                Key::Down => state.right_player.direction = 1,
-                _ => (),
-            }
-        }
-
-        fps_counter.update();
-        if app_state.step() % 2 == 0 {
-            state.update();
+            pencil.set_foreground(Color::DarkGrey);
+            pencil.draw_text(
+                "up/down: choose - space: change",
+                Vec2::xy((self.dimension.x - 31) / 2, top + SETUP_ROWS as i32 + 1),
+            );
+            return;
         }
 
         let score_msg = &format!(
-            "Left score: {}  -  Right score: {}",
-            state.left_player.score, state.right_player.score
+            "Left score: {}  -  Right score: {}  -  {}",
+            self.left_player.score,
+            self.right_player.score,
+            self.status()
         );
 
-        Pencil::new(window.canvas_mut())
-            .draw_text(&format!("FPS: {}", fps_counter.count()), Vec2::xy(0, 0))
-            .set_origin(Vec2::xy(
-                (win_size.x - score_msg.len() as i32) / 2,
-                (win_size.y - state.dimension.y) / 2 - 1,
-            ))
-            .draw_text(score_msg, Vec2::xy(0, 0))
-            .set_origin((win_size - state.dimension) / 2)
+        pencil
+            .draw_text(
+                score_msg,
+                Vec2::xy((self.dimension.x - score_msg.len() as i32) / 2, -1),
+            )
             .draw_rect(
                 &RectCharset::simple_round_lines(),
                 Vec2::zero(),
-                state.dimension,
+                self.dimension,
             )
             .draw_vline(
                 '\'',
-                Vec2::xy(state.dimension.x / 2, 1),
-                state.dimension.y - 2,
+                Vec2::xy(self.dimension.x / 2, 1),
+                self.dimension.y - 2,
             )
             .set_foreground(Color::Blue)
             .draw_rect(
                 &RectCharset::double_lines(),
-                state.left_player.position - Vec2::y(PAD_HEIGHT),
+                self.left_player.position - Vec2::y(PAD_HEIGHT),
                 Vec2::xy(2, PAD_HEIGHT * 2),
             )
             .set_foreground(Color::Red)
             .draw_rect(
                 &RectCharset::double_lines(),
-                state.right_player.position - Vec2::y(PAD_HEIGHT),
+                self.right_player.position - Vec2::y(PAD_HEIGHT),
                 Vec2::xy(2, PAD_HEIGHT * 2),
             )
             .set_foreground(Color::Yellow)
             .set_style(Style::Bold)
-            .draw_char('o', state.ball_position);
-    });
-}
\ No newline at end of file
+            .draw_char('o', self.ball_position.to_cell());
+
+        // Show who won once the match is over, above the high scores
+        if let Some(winner) = self.winner() {
+            let player = match winner {
+                Side::Left => &self.left_player,
+                Side::Right => &self.right_player,
+            };
+            let who = match player.cpu {
+                Some(_) => format!("the computer ({})", winner.name()),
+                None => format!("the {} player", winner.name()),
+            };
+            let msg = format!(
+                "MATCH OVER - {} wins {} - {}",
+                who,
+                player.score,
+                self.left_player.score.min(self.right_player.score)
+            );
+            pencil.draw_text(&msg, Vec2::xy((self.dimension.x - msg.len() as i32) / 2, 2));
+        }
+    }
+
+    // The best score of a player (the computer's points do not count).
+    fn score(&self) -> usize {
+        [&self.left_player, &self.right_player]
+            .iter()
+            .filter(|player| player.cpu.is_none())
+            .map(|player| player.score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    fn mode(&self) -> String {
+        match (&self.left_player.cpu, &self.right_player.cpu) {
+            (None, None) => "2 players".to_string(),
+            (Some(cpu), None) | (None, Some(cpu)) => format!("vs cpu {}", cpu.difficulty.name()),
+            (Some(_), Some(_)) => "demo".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winner() {
+        let rules = MatchRules::default();
+        assert_eq!(rules.winner(0, 0), None);
+        assert_eq!(rules.winner(11, 9), Some(Side::Left));
+        assert_eq!(rules.winner(3, 11), Some(Side::Right));
+        assert_eq!(rules.winner(11, 10), None);
+        assert_eq!(rules.winner(12, 10), Some(Side::Left));
+        assert_eq!(rules.winner(15, 16), None);
+        assert_eq!(rules.winner(15, 17), Some(Side::Right));
+
+        let sudden_death = MatchRules { points_to_win: 5, win_by: 1 };
+        assert_eq!(sudden_death.winner(5, 4), Some(Side::Left));
+        assert_eq!(sudden_death.winner(4, 4), None);
+    }
+
+    #[test]
+    fn is_deuce() {
+        let rules = MatchRules::default();
+        assert!(!rules.is_deuce(9, 10));
+        assert!(!rules.is_deuce(10, 3));
+        assert!(rules.is_deuce(10, 10));
+        assert!(rules.is_deuce(11, 10));
+        assert!(rules.is_deuce(14, 13));
+    }
+
+    fn court() -> GameState {
+        let mut game = GameState::new(Vec2::xy(100, 40), 1);
+        game.left_player.position.y = 10;
+        game.right_player.position.y = 10;
+        game
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn return_ball_from_the_middle_of_the_paddle() {
+        let mut game = court();
+        game.return_ball(Side::Left, 9.5);
+        assert_eq!(game.rally, 1);
+        assert!(close(game.ball_speed.x, BALL_SPEED * RALLY_SPEED_RAMP));
+        assert!(close(game.ball_speed.y, 0.0));
+
+        game.return_ball(Side::Right, 9.5);
+        assert_eq!(game.rally, 2);
+        assert!(close(game.ball_speed.x, -BALL_SPEED * RALLY_SPEED_RAMP * RALLY_SPEED_RAMP));
+    }
+
+    #[test]
+    fn return_ball_angle_and_spin() {
+        let mut game = court();
+        // The end of the paddle sends the ball off at the steepest angle
+        game.return_ball(Side::Left, 13.0);
+        assert!(close(game.ball_speed.y.atan2(game.ball_speed.x), MAX_BOUNCE_ANGLE));
+
+        // A paddle moving up turns a ball hit in its middle upwards
+        game.left_player.direction = -1;
+        game.return_ball(Side::Left, 9.5);
+        assert!(close(game.ball_speed.y.atan2(game.ball_speed.x), -SPIN));
+
+        // Even with spin, no steeper than the steepest angle
+        game.left_player.direction = 1;
+        game.return_ball(Side::Left, 13.0);
+        assert!(close(game.ball_speed.y.atan2(game.ball_speed.x), MAX_BOUNCE_ANGLE));
+    }
+
+    #[test]
+    fn rally_speed_is_capped_and_reset_by_a_point() {
+        let mut game = court();
+        for _ in 0..50 {
+            game.return_ball(Side::Left, 9.5);
+        }
+        assert!(close(game.ball_speed.length(), MAX_BALL_SPEED));
+
+        game.win_point(Side::Left);
+        assert_eq!(game.rally, 0);
+        assert_eq!(game.ball_speed, Vec2f::zero());
+        game.return_ball(Side::Right, 9.5);
+        assert!(close(game.ball_speed.length(), BALL_SPEED * RALLY_SPEED_RAMP));
+    }
+}
@@ -0,0 +1,192 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use ruscii::spatial::Vec2;
+
+use crate::physics::Vec2f;
+
+// How well the computer plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    // How many ticks go by before the computer looks at the ball again.
+    fn reaction_delay(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 8,
+            Difficulty::Normal => 4,
+            Difficulty::Hard => 1,
+        }
+    }
+
+    // How fast the computer can move its paddle, in cells per tick.
+    fn max_speed(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 0.75,
+            Difficulty::Hard => 1.0,
+        }
+    }
+
+    // How far off (in cells) its guess of where the ball goes can be.
+    fn prediction_error(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 4.0,
+            Difficulty::Normal => 2.0,
+            Difficulty::Hard => 0.5,
+        }
+    }
+}
+
+// Where the ball will be once it gets to `x`, bouncing off the top and
+// bottom walls (at 0 and `height`) on the way.
+fn predict_y(ball_position: Vec2f, ball_speed: Vec2f, x: f32, height: f32) -> f32 {
+    if ball_speed.x == 0.0 || height <= 0.0 {
+        return ball_position.y;
+    }
+    let ticks = ((x - ball_position.x) / ball_speed.x).max(0.0);
+    let y = ball_position.y + ball_speed.y * ticks;
+
+    // Fold the straight line back between the walls
+    let period = 2.0 * height;
+    let y = y.rem_euclid(period);
+    if y > height {
+        period - y
+    } else {
+        y
+    }
+}
+
+/*
+    Cpu defines the computer moving one of the paddles.
+    - how well it plays
+    - the height it is moving the paddle to
+    - the ticks left before it looks at the ball again
+    - the movement it saved up (as it may move slower than a cell per tick)
+*/
+#[derive(Clone, Debug)]
+pub struct Cpu {
+    pub difficulty: Difficulty,
+    target: f32,
+    wait: u32,
+    moves: f32,
+}
+
+impl Cpu {
+    pub fn new(difficulty: Difficulty) -> Cpu {
+        Cpu {
+            difficulty,
+            target: 0.0,
+            wait: 0,
+            moves: 0.0,
+        }
+    }
+
+    // Decides which way (-1, 0 or 1) to move the paddle at `paddle` this tick.
+    // When the ball comes towards the paddle, the computer goes to where it
+    // guesses the ball will be, otherwise it goes back to the middle.
+    pub fn direction(
+        &mut self,
+        paddle: Vec2,
+        ball_position: Vec2f,
+        ball_speed: Vec2f,
+        dimension: Vec2,
+        rng: &mut StdRng,
+    ) -> i32 {
+        if self.wait == 0 {
+            self.wait = self.difficulty.reaction_delay();
+            let on_the_left = paddle.x < dimension.x / 2;
+            let coming = (ball_speed.x < 0.0) == on_the_left;
+            self.target = if coming {
+                let error = self.difficulty.prediction_error();
+                let height = (dimension.y - 1) as f32;
+                predict_y(ball_position, ball_speed, paddle.x as f32, height) + rng.gen_range(-error..=error)
+            } else {
+                dimension.y as f32 / 2.0
+            };
+        }
+        self.wait -= 1;
+
+        self.moves = (self.moves + self.difficulty.max_speed()).min(1.0);
+        let distance = self.target - paddle.y as f32;
+        if distance.abs() < 1.0 || self.moves < 1.0 {
+            return 0;
+        }
+        self.moves -= 1.0;
+        distance.signum() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const DIMENSION: Vec2 = Vec2 { x: 40, y: 21 };
+    const RIGHT_PADDLE: Vec2 = Vec2 { x: 37, y: 5 };
+
+    #[test]
+    fn predict_y_without_bounces() {
+        assert_eq!(predict_y(Vec2f::xy(10.0, 5.0), Vec2f::xy(1.0, 0.5), 20.0, 20.0), 10.0);
+        // A ball going away is where it is now
+        assert_eq!(predict_y(Vec2f::xy(10.0, 5.0), Vec2f::xy(-1.0, 0.5), 20.0, 20.0), 5.0);
+        assert_eq!(predict_y(Vec2f::xy(10.0, 5.0), Vec2f::xy(0.0, 0.5), 20.0, 20.0), 5.0);
+    }
+
+    #[test]
+    fn predict_y_bounces_off_the_walls() {
+        assert_eq!(predict_y(Vec2f::xy(0.0, 18.0), Vec2f::xy(1.0, 1.0), 4.0, 20.0), 18.0);
+        assert_eq!(predict_y(Vec2f::xy(0.0, 2.0), Vec2f::xy(1.0, -1.0), 5.0, 20.0), 3.0);
+        // Off the bottom and then the top
+        assert_eq!(predict_y(Vec2f::xy(0.0, 10.0), Vec2f::xy(1.0, 1.0), 35.0, 20.0), 5.0);
+    }
+
+    #[test]
+    fn follows_a_ball_coming_towards_it() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut cpu = Cpu::new(Difficulty::Hard);
+        let (ball, speed) = (Vec2f::xy(20.0, 15.0), Vec2f::xy(1.0, 0.0));
+        assert_eq!(cpu.direction(RIGHT_PADDLE, ball, speed, DIMENSION, &mut rng), 1);
+
+        let paddle = Vec2::xy(RIGHT_PADDLE.x, 15);
+        assert_eq!(cpu.direction(paddle, ball, speed, DIMENSION, &mut rng), 0);
+    }
+
+    #[test]
+    fn goes_back_to_the_middle_when_the_ball_goes_away() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut cpu = Cpu::new(Difficulty::Hard);
+        let paddle = Vec2::xy(RIGHT_PADDLE.x, 18);
+        let direction = cpu.direction(paddle, Vec2f::xy(20.0, 18.0), Vec2f::xy(-1.0, 0.0), DIMENSION, &mut rng);
+        assert_eq!(direction, -1);
+    }
+
+    #[test]
+    fn easy_moves_every_other_tick() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut cpu = Cpu::new(Difficulty::Easy);
+        let (ball, speed) = (Vec2f::xy(20.0, 19.0), Vec2f::xy(1.0, 0.0));
+        let moves: Vec<i32> =
+            (0..4).map(|_| cpu.direction(RIGHT_PADDLE, ball, speed, DIMENSION, &mut rng)).collect();
+        assert_eq!(moves, [0, 1, 0, 1]);
+    }
+}
@@ -1,223 +1,796 @@
-use ruscii::app::{App, State};
+pub mod aliens;
+pub mod bunkers;
+
 use ruscii::drawing::Pencil;
-use ruscii::gui::FPSCounter;
-use ruscii::keyboard::{Key, KeyEvent};
+use ruscii::keyboard::Key;
 use ruscii::spatial::Vec2;
-use ruscii::terminal::{Color, Style, Window};
+use ruscii::terminal::{Color, Style};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::bindings::{Action, ActionEvent};
+use crate::collision::Rect;
+use crate::game::Game;
+use crate::physics::Vec2f;
+
+use self::aliens::{formation, is_bottom_of_column, Alien, AlienShot, ShotKind, Ufo};
+use self::bunkers::{build_bunkers, chip, hit_time, BunkerCell};
+
+const DIMENSION: Vec2 = Vec2 { x: 50, y: 22 };
+// The field plus its border
+const MIN_WINDOW: Vec2 = Vec2 { x: DIMENSION.x + 2, y: DIMENSION.y + 2 };
+
+// Cadences of the game, in ticks (there are 30 ticks per second)
+const SHOT_COOLDOWN: usize = 15;
+const ALIENS_SHOTS_PERIOD: usize = 5;
+// How likely an alien at the bottom of its column is to fire every time the
+// shots move (1 in ALIENS_FIRE_CHANCE), and how many of their shots can be on
+// the field at once
+const ALIENS_FIRE_CHANCE: u32 = 40;
+const MAX_ALIENS_SHOTS: usize = 3;
+// How many ticks a full formation of the first wave takes to move by one
+// cell, how much faster it is every wave, and how fast a wave can start
+const ALIENS_MOVEMENT_PERIOD: usize = 20;
+const WAVE_SPEED_UP: usize = 2;
+const MIN_WAVE_MOVEMENT_PERIOD: usize = 8;
+// The top row of the aliens of the first wave, and how much lower the
+// following waves start (never lower than MAX_WAVE_DROP)
+const ALIENS_TOP: i32 = 2;
+const MAX_WAVE_DROP: i32 = 4;
+// An extra life is given every time the score goes past a multiple of this
+const EXTRA_LIFE_SCORE: usize = 1500;
+// The mystery ship comes by every 20 to 30 seconds
+const UFO_PERIOD: std::ops::Range<usize> = 600..900;
+// How long the bonus of a mystery ship that was shot is shown
+const UFO_BONUS_TICKS: usize = 45;
+// How long the game waits before a player takes their turn
+const TURN_PAUSE_TICKS: usize = 60;
+const LIVES: usize = 3;
 
-use rand::{self, prelude::*};
+/*
+    PlayerState defines everything that belongs to one player, which is put
+    aside while the other player takes their turn.
+    - the aliens of their formation and which way it is moving
+    - their bunkers
+    - the wave they are on and how many aliens it started with
+    - how many lives they have left, and the score of their next extra life
+    - their score
+*/
+pub struct PlayerState {
+    pub aliens: Vec<Alien>,
+    pub aliens_movement: (i32, bool), //dir, just_down
+    pub bunkers: Vec<BunkerCell>,
+    pub wave: usize,
+    pub wave_size: usize,
+    pub lives: usize,
+    pub next_extra_life: usize,
+    pub score: usize,
+}
 
-struct GameState {
+impl PlayerState {
+    pub fn new(dimension: Vec2) -> PlayerState {
+        let aliens = formation(ALIENS_TOP, dimension.x);
+        PlayerState {
+            wave_size: aliens.len(),
+            aliens,
+            aliens_movement: (1, false),
+            bunkers: build_bunkers(dimension.x, dimension.y - 2),
+            wave: 1,
+            lives: LIVES,
+            next_extra_life: EXTRA_LIFE_SCORE,
+            score: 0,
+        }
+    }
+
+    // Aliens move faster every wave, and faster as there are fewer of them
+    // left: the last one moves every tick.
+    pub fn aliens_movement_period(&self) -> usize {
+        let wave_period = ALIENS_MOVEMENT_PERIOD
+            .saturating_sub(WAVE_SPEED_UP * (self.wave - 1))
+            .max(MIN_WAVE_MOVEMENT_PERIOD);
+        (wave_period * self.aliens.len() / self.wave_size.max(1)).max(1)
+    }
+
+    // Brings in the formation of the next wave, a bit lower than the last
+    // one, behind new bunkers.
+    pub fn start_wave(&mut self, dimension: Vec2) {
+        self.wave += 1;
+        let drop = (self.wave as i32 - 1).min(MAX_WAVE_DROP);
+        self.aliens = formation(ALIENS_TOP + drop, dimension.x);
+        self.wave_size = self.aliens.len();
+        self.aliens_movement = (1, false);
+        self.bunkers = build_bunkers(dimension.x, dimension.y - 2);
+    }
+}
+
+/*
+    GameState defines the state of a game of Space Invaders.
+    - the size of the field
+    - the spaceship, where it was on the last tick, its shots and when it last fired
+    - the animation frame of the aliens, their shots and when they last moved and fired
+    - the mystery ship, when the next one comes and the bonus of the last one shot
+    - the player whose turn it is, and the other one in a two-player game
+    - the number of players chosen on the setup screen, until the game starts
+    - how many ticks are left before the player takes their turn
+*/
+pub struct GameState {
     pub dimension: Vec2,
     pub spaceship: Vec2,
+    pub last_spaceship: Vec2,
     pub spaceship_shots: Vec<Vec2>,
-    pub last_shot_frame: usize,
-    pub aliens: Vec<Vec2>,
-    pub aliens_shots: Vec<Vec2>,
-    pub aliens_movement: (i32, bool), //dir, just_down
+    pub last_shot_tick: usize,
+    pub aliens_frame: bool,
+    pub aliens_shots: Vec<AlienShot>,
     pub last_aliens_movement: usize,
     pub last_aliens_shots: usize,
-    pub lives: usize,
-    pub score: usize,
+    pub ufo: Option<Ufo>,
+    pub next_ufo_tick: usize,
+    pub ufo_bonus: Option<(Vec2, usize, usize)>, //position, points, until tick
+    pub player: PlayerState,
+    pub player_number: usize,
+    pub waiting: Option<PlayerState>,
+    pub setup: Option<usize>,
+    pub turn_pause: usize,
+    pub tick: usize,
+    pub rng: StdRng,
 }
 
 impl GameState {
-    pub fn new(dimension: Vec2) -> GameState {
-        let mut aliens = Vec::new();
-        for y in 2..7 {
-            for x in 5..dimension.x - 5 {
-                if x % 2 != 0 {
-                    aliens.push(Vec2::xy(x, y));
-                }
-            }
+    pub fn spaceship_move_x(&mut self, displacement: i32) {
+        if displacement < 0 && self.spaceship.x != 0
+            || displacement > 0 && self.spaceship.x != self.dimension.x
+        {
+            self.spaceship.x += displacement;
         }
+    }
+
+    // The box the spaceship went through since the last tick, so that it
+    // cannot jump over a shot when it moves several cells at once.
+    pub fn spaceship_rect(&self) -> Rect {
+        let left = self.spaceship.x.min(self.last_spaceship.x) - 1;
+        let width = (self.spaceship.x - self.last_spaceship.x).abs() + 3;
+        Rect::from_cells(Vec2::xy(left, self.spaceship.y), Vec2::xy(width, 1))
+    }
+
+    // Starts the game with the number of players chosen on the setup screen.
+    fn start(&mut self) {
+        if self.setup.take() == Some(2) {
+            self.waiting = Some(PlayerState::new(self.dimension));
+            self.turn_pause = TURN_PAUSE_TICKS;
+        }
+    }
+
+    pub fn is_two_players(&self) -> bool {
+        self.waiting.is_some()
+    }
+
+    // The state of player 1 or 2, whether it is their turn or not.
+    pub fn player_state(&self, number: usize) -> Option<&PlayerState> {
+        if number == self.player_number {
+            Some(&self.player)
+        } else {
+            self.waiting.as_ref()
+        }
+    }
+
+    // Clears the field of everything that moves and puts the spaceship back
+    // in the middle, for the player taking their turn.
+    fn clear_field(&mut self) {
+        self.aliens_shots.clear();
+        self.spaceship_shots.clear();
+        self.ufo = None;
+        self.ufo_bonus = None;
+        self.spaceship = Vec2::xy(self.dimension.x / 2, self.dimension.y - 2);
+        self.last_spaceship = self.spaceship;
+    }
+
+    // Hands over to the other player after a life was lost, if they have any
+    // lives left. Returns whether the turn changed.
+    fn switch_turn(&mut self) -> bool {
+        let Some(waiting) = &mut self.waiting else {
+            return false;
+        };
+        if waiting.lives == 0 {
+            return false;
+        }
+        std::mem::swap(&mut self.player, waiting);
+        self.player_number = 3 - self.player_number;
+        self.clear_field();
+        self.next_ufo_tick = self.tick + self.rng.gen_range(UFO_PERIOD);
+        self.turn_pause = TURN_PAUSE_TICKS;
+        true
+    }
+
+    pub fn spaceship_shot(&mut self) {
+        if self.last_shot_tick + SHOT_COOLDOWN < self.tick {
+            self.spaceship_shots.push(self.spaceship);
+            self.last_shot_tick = self.tick;
+        }
+    }
+}
+
+impl Game for GameState {
+    const NAME: &'static str = "Space Invaders";
+    const TICKS_PER_SECOND: u32 = 30;
+    const MIN_WINDOW: Vec2 = MIN_WINDOW;
+    const BINDINGS: &'static [(Action, &'static [Key])] = &[
+        (Action::MoveLeft, &[Key::A, Key::H, Key::Left]),
+        (Action::MoveRight, &[Key::D, Key::L, Key::Right]),
+        (Action::Fire, &[Key::Space]),
+    ];
+
+    // Space Invaders is played on a fixed size field whatever the window size,
+    // so it is letterboxed in bigger windows.
+    fn new(_win_size: Vec2, seed: u64) -> GameState {
+        let dimension = DIMENSION;
+        let mut rng = StdRng::seed_from_u64(seed);
         GameState {
             dimension,
             spaceship: Vec2::xy(dimension.x / 2, dimension.y - 2),
+            last_spaceship: Vec2::xy(dimension.x / 2, dimension.y - 2),
             spaceship_shots: Vec::new(),
-            last_shot_frame: 0,
-            aliens,
+            last_shot_tick: 0,
+            aliens_frame: false,
             aliens_shots: Vec::new(),
-            aliens_movement: (1, false),
             last_aliens_movement: 0,
             last_aliens_shots: 0,
-            lives: 3,
-            score: 0,
+            ufo: None,
+            next_ufo_tick: rng.gen_range(UFO_PERIOD),
+            ufo_bonus: None,
+            player: PlayerState::new(dimension),
+            player_number: 1,
+            waiting: None,
+            setup: Some(1),
+            turn_pause: 0,
+            tick: 0,
+            rng,
         }
     }
 
-    pub fn spaceship_move_x(&mut self, displacement: i32) {
-        if displacement < 0 && self.spaceship.x != 0
-            || displacement > 0 && self.spaceship.x != self.dimension.x
-        {
-            self.spaceship.x += displacement;
+    fn dimension(&self) -> Vec2 {
+        self.dimension
+    }
+
+    // The setup screen chooses between one and two players
+    fn handle_action_event(&mut self, event: &ActionEvent) {
+        let Some(players) = &mut self.setup else {
+            return;
+        };
+        match event {
+            ActionEvent::Pressed(Action::MoveLeft | Action::MoveRight) => *players = 3 - *players,
+            ActionEvent::Pressed(Action::Fire) => self.start(),
+            _ => (),
         }
     }
 
-    pub fn spaceship_shot(&mut self, shot_frame: usize) {
-        if self.last_shot_frame + 15 < shot_frame {
-            self.spaceship_shots.push(self.spaceship);
-            self.last_shot_frame = shot_frame;
+    fn handle_actions_down(&mut self, actions: &[Action]) {
+        if self.setup.is_some() || self.turn_pause > 0 {
+            return;
+        }
+        for action in actions {
+            match action {
+                Action::MoveLeft => self.spaceship_move_x(-5),
+                Action::MoveRight => self.spaceship_move_x(5),
+                Action::Fire => self.spaceship_shot(),
+                _ => (),
+            }
         }
     }
 
-    pub fn update(&mut self, frame: usize) {
+    fn update(&mut self) {
+        if self.setup.is_some() || self.is_finished() {
+            return;
+        }
+        if self.turn_pause > 0 {
+            self.turn_pause -= 1;
+            return;
+        }
+        self.tick += 1;
+        let tick = self.tick;
+
+        // Move the shots of the spaceship up, chipping the bunkers and
+        // destroying the first alien in their way (including one that moved
+        // onto them) or the mystery ship
         let mut partial_score = 0;
-        let aliens = &mut self.aliens;
-        self.spaceship_shots.retain(|shot| {
-            if shot.y == 1 {
+        let aliens = &mut self.player.aliens;
+        let bunkers = &mut self.player.bunkers;
+        let ufo = &mut self.ufo;
+        let mut ufo_bonus = None;
+        self.spaceship_shots.retain_mut(|shot| {
+            let start = Vec2f::from(*shot);
+            let motion = Vec2f::xy(0.0, -1.0);
+            if chip(bunkers, start, motion) {
                 return false;
             }
-            let pre_len = aliens.len();
-            aliens.retain(|alien| alien != shot);
-            let destroyed = aliens.len() != pre_len;
-            if destroyed {
-                partial_score += 5;
+            let hit = aliens.iter().position(|alien| hit_time(alien.position, start, motion).is_some());
+            if let Some(index) = hit {
+                partial_score += aliens.remove(index).kind.score();
+                return false;
+            }
+            let hit = ufo.as_ref().filter(|ufo| ufo.is_hit(start, motion));
+            if let Some((position, points)) = hit.map(|ufo| (ufo.position, ufo.score)) {
+                *ufo = None;
+                partial_score += points;
+                ufo_bonus = Some((position, points, tick + UFO_BONUS_TICKS));
+                return false;
+            }
+            if shot.y == 1 {
+                return false;
             }
-            !destroyed
+            shot.y -= 1;
+            true
         });
-        self.score += partial_score;
+        self.player.score += partial_score;
+        if ufo_bonus.is_some() {
+            self.ufo_bonus = ufo_bonus;
+            self.next_ufo_tick = tick + self.rng.gen_range(UFO_PERIOD);
+        }
+        if self.ufo_bonus.is_some_and(|(_, _, until)| until <= tick) {
+            self.ufo_bonus = None;
+        }
 
-        self.spaceship_shots.iter_mut().for_each(|shot| shot.y -= 1);
+        // Send the mystery ship across the top of the field now and then
+        if let Some(ufo) = &mut self.ufo {
+            if !ufo.fly(tick, self.dimension.x) {
+                self.ufo = None;
+                self.next_ufo_tick = tick + self.rng.gen_range(UFO_PERIOD);
+            }
+        } else if tick >= self.next_ufo_tick {
+            self.ufo = Some(Ufo::new(self.dimension.x, 1, &mut self.rng));
+        }
 
-        if self.last_aliens_shots + 5 < frame {
-            self.last_aliens_shots = frame;
-            for alien in &self.aliens {
-                let must_shot = thread_rng().gen_range(0..=200) == 0;
+        if self.last_aliens_shots + ALIENS_SHOTS_PERIOD < tick {
+            self.last_aliens_shots = tick;
+            // Only the lowest alien of each column fires, so that shots
+            // never go through the formation. They get their chance in a
+            // random order, so that the cap does not favour any column.
+            let aliens = &self.player.aliens;
+            let mut shooters: Vec<Vec2> = aliens
+                .iter()
+                .filter(|alien| is_bottom_of_column(alien, aliens))
+                .map(|alien| alien.position)
+                .collect();
+            shooters.shuffle(&mut self.rng);
+            for position in shooters {
+                if self.aliens_shots.len() >= MAX_ALIENS_SHOTS {
+                    break;
+                }
+                let must_shot = self.rng.gen_range(0..ALIENS_FIRE_CHANCE) == 0;
                 if must_shot {
-                    self.aliens_shots.push(*alien);
+                    let kind = ShotKind::random(self.spaceship.x, &mut self.rng);
+                    self.aliens_shots.push(AlienShot::new(position, kind));
                 }
             }
 
-            let bottom_shot_limit = self.dimension.y;
-            self.aliens_shots.retain(|shot| shot.y < bottom_shot_limit);
-            self.aliens_shots.iter_mut().for_each(|shot| shot.y += 1);
+            // Move the shots of the aliens down, unless a bunker stops them
+            let dimension = self.dimension;
+            let bunkers = &mut self.player.bunkers;
+            self.aliens_shots.retain_mut(|shot| {
+                let position = shot.position;
+                if position.y >= dimension.y || position.x < 0 || position.x > dimension.x {
+                    return false;
+                }
+                let motion = shot.motion();
+                if chip(bunkers, Vec2f::from(shot.position), Vec2f::from(motion)) {
+                    return false;
+                }
+                shot.position += motion;
+                true
+            });
         }
 
         let mut damage = 0;
-        let spaceship = &self.spaceship;
+        let spaceship = self.spaceship_rect();
         self.aliens_shots.retain(|shot| {
-            if shot.y == spaceship.y
-                && (shot.x == spaceship.x || shot.x == spaceship.x + 1 || shot.x == spaceship.x - 1)
-            {
+            if spaceship.contains(Vec2f::from(shot.position)) {
                 damage += 1;
                 return false;
             }
             true
         });
 
-        self.aliens.iter().for_each(|alien| {
-            if alien.y == spaceship.y
-                && (alien.x == spaceship.x
-                    || alien.x == spaceship.x + 1
-                    || alien.x == spaceship.x - 1)
-            {
-                damage = 1000;
-            }
-        });
+        if self
+            .player
+            .aliens
+            .iter()
+            .any(|alien| spaceship.intersects(&Rect::from_cells(alien.position, Vec2::xy(1, 1))))
+        {
+            damage = 1000;
+        }
+        self.last_spaceship = self.spaceship;
 
-        self.lives = if damage >= self.lives {
-            0
-        } else {
-            self.lives - damage
-        };
+        self.player.lives = self.player.lives.saturating_sub(damage);
+
+        // In a two-player game, losing a life hands over to the other player
+        if damage > 0 && self.switch_turn() {
+            return;
+        }
 
-        if self.aliens.len() > 0 {
-            let left = self.aliens.iter().min_by_key(|alien| alien.x).unwrap();
-            let right = self.aliens.iter().max_by_key(|alien| alien.x).unwrap();
-            if self.last_aliens_movement + 20 < frame {
-                self.last_aliens_movement = frame;
-
-                if left.x == 0 || right.x == self.dimension.x {
-                    if self.aliens_movement.1 {
-                        self.aliens_movement.0 = -self.aliens_movement.0;
-                        let dir = self.aliens_movement.0;
-                        self.aliens
+        let player = &mut self.player;
+        if !player.aliens.is_empty() {
+            let left = player.aliens.iter().map(|alien| alien.position.x).min().unwrap();
+            let right = player.aliens.iter().map(|alien| alien.position.x).max().unwrap();
+            if self.last_aliens_movement + player.aliens_movement_period() < tick {
+                self.last_aliens_movement = tick;
+                self.aliens_frame = !self.aliens_frame;
+
+                if left == 0 || right == self.dimension.x {
+                    if player.aliens_movement.1 {
+                        player.aliens_movement.0 = -player.aliens_movement.0;
+                        let dir = player.aliens_movement.0;
+                        player.aliens
                             .iter_mut()
-                            .for_each(|alien| alien.x = alien.x + dir);
-                        self.aliens_movement.1 = false;
+                            .for_each(|alien| alien.position.x += dir);
+                        player.aliens_movement.1 = false;
                     } else {
-                        self.aliens.iter_mut().for_each(|alien| alien.y += 1);
-                        self.aliens_movement.1 = true;
+                        player.aliens.iter_mut().for_each(|alien| alien.position.y += 1);
+                        player.aliens_movement.1 = true;
                     }
                 } else {
-                    let dir = self.aliens_movement.0;
-                    self.aliens
+                    let dir = player.aliens_movement.0;
+                    player.aliens
                         .iter_mut()
-                        .for_each(|alien| alien.x = alien.x + dir);
+                        .for_each(|alien| alien.position.x += dir);
                 }
             }
         }
-    }
-}
 
-fn main() {
-    let mut app = App::default();
-    let mut state = GameState::new(Vec2::xy(50, 22));
-    let mut fps_counter = FPSCounter::default();
+        // Aliens crush the bunkers they run into
+        let aliens = &player.aliens;
+        player.bunkers.retain(|cell| !aliens.iter().any(|alien| alien.position == cell.position));
 
-    app.run(|app_state: &mut State, window: &mut Window| {
-        for key_event in app_state.keyboard().last_key_events() {
-            match key_event {
-                KeyEvent::Pressed(Key::Esc) => app_state.stop(),
-                KeyEvent::Pressed(Key::Q) => app_state.stop(),
-                _ => (),
-            }
+        // Give an extra life for every threshold the score went past
+        while player.score >= player.next_extra_life {
+            player.lives += 1;
+            player.next_extra_life += EXTRA_LIFE_SCORE;
         }
 
-        for key_down in app_state.keyboard().get_keys_down() {
-            match key_down {
-                Key::A | Key::H => state.spaceship_move_x(-5),
-                Key::D | Key::L => state.spaceship_move_x(5),
-                Key::Space => state.spaceship_shot(app_state.step()),
-                _ => (),
-            }
+        // The next wave comes in once every alien is shot down
+        if player.aliens.is_empty() && player.lives > 0 {
+            player.start_wave(self.dimension);
+            self.aliens_shots.clear();
+            self.spaceship_shots.clear();
         }
+    }
 
-        state.update(app_state.step());
-        fps_counter.update();
-
-        let win_size = window.size();
-        let mut pencil = Pencil::new(window.canvas_mut());
-        pencil.draw_text(&format!("FPS: {}", fps_counter.count()), Vec2::xy(1, 0));
+    fn render(&self, pencil: &mut Pencil) {
+        if let Some(players) = self.setup {
+            let top = self.dimension.y / 2 - 3;
+            pencil.set_style(Style::Bold);
+            let title = "S P A C E   I N V A D E R S";
+            pencil.draw_text(title, Vec2::xy((self.dimension.x - title.len() as i32) / 2, top));
+            pencil.set_style(Style::Plain);
+            for (index, line) in ["1 player", "2 players"].iter().enumerate() {
+                let position = Vec2::xy((self.dimension.x - 9) / 2, top + 2 + index as i32);
+                if index + 1 == players {
+                    pencil.set_foreground(Color::Yellow);
+                    pencil.draw_text(&format!("> {}", line), position - Vec2::x(2));
+                } else {
+                    pencil.set_foreground(Color::White);
+                    pencil.draw_text(line, position);
+                }
+            }
+            pencil.set_foreground(Color::DarkGrey);
+            let hint = "a/d or arrows: choose - space: start";
+            pencil.draw_text(hint, Vec2::xy((self.dimension.x - hint.len() as i32) / 2, top + 5));
+            return;
+        }
 
-        if state.aliens.is_empty() || state.lives == 0 {
-            let status_msg = if state.lives > 0 {
-                "You win! :D"
-            } else {
-                "You lose :("
+        let player = &self.player;
+        let hud = if self.is_two_players() {
+            // The player whose turn it is is marked with a `>`
+            let score = |number| {
+                let marker = if number == self.player_number { ">" } else { " " };
+                let score = self.player_state(number).map_or(0, |player| player.score);
+                format!("{}{}UP: {}", marker, number, score)
             };
-            let msg = &format!("{}  -  score: {}", status_msg, state.score);
-            pencil.set_origin(win_size / 2 - Vec2::x(msg.len() / 2));
-            pencil.draw_text(msg, Vec2::zero());
-            return ();
+            format!(
+                "{} {}  -  wave: {}  -  lives: {}",
+                score(1),
+                score(2),
+                player.wave,
+                player.lives
+            )
+        } else {
+            format!("wave: {}  -  lives: {}  -  score: {}", player.wave, player.lives, player.score)
+        };
+        pencil.draw_text(&hud, Vec2::xy((self.dimension.x - hud.len() as i32) / 2, 0));
+        if self.turn_pause > 0 {
+            let msg = format!("PLAYER {}", self.player_number);
+            pencil.set_foreground(Color::Yellow);
+            let x = (self.dimension.x - msg.len() as i32) / 2;
+            pencil.draw_text(&msg, Vec2::xy(x, self.dimension.y / 2));
         }
 
-        pencil.set_origin((win_size - state.dimension) / 2);
-        pencil.draw_text(
-            &format!("lives: {}  -  score: {}", state.lives, state.score),
-            Vec2::xy(15, 0),
-        );
         pencil.set_foreground(Color::Cyan);
-        pencil.draw_char('^', state.spaceship);
-        pencil.draw_char('/', state.spaceship - Vec2::x(1));
-        pencil.draw_char('\\', state.spaceship + Vec2::x(1));
-        pencil.draw_char('\'', state.spaceship + Vec2::y(1));
+        pencil.draw_char('^', self.spaceship);
+        pencil.draw_char('/', self.spaceship - Vec2::x(1));
+        pencil.draw_char('\\', self.spaceship + Vec2::x(1));
+        pencil.draw_char('\'', self.spaceship + Vec2::y(1));
+
+        pencil.set_foreground(Color::Green);
+        for cell in &player.bunkers {
+            pencil.draw_char(cell.glyph(), cell.position);
+        }
 
         pencil.set_foreground(Color::Red);
-        for shot in &state.aliens_shots {
-            pencil.draw_char('|', *shot);
+        for shot in &self.aliens_shots {
+            pencil.draw_char(shot.glyph(), shot.position);
         }
 
-        pencil.set_foreground(Color::Green);
-        for alien in &state.aliens {
-            pencil.draw_char('W', *alien);
+        for alien in &player.aliens {
+            pencil.set_foreground(alien.kind.color());
+            pencil.draw_char(alien.kind.glyph(self.aliens_frame), alien.position);
+        }
+
+        pencil.set_foreground(Color::Red);
+        if let Some(ufo) = &self.ufo {
+            pencil.draw_text(Ufo::GLYPH, ufo.position - Vec2::x(1));
+        }
+        if let Some((position, points, _)) = self.ufo_bonus {
+            pencil.draw_text(&points.to_string(), position - Vec2::x(1));
         }
 
         pencil.set_foreground(Color::Yellow);
         pencil.set_style(Style::Bold);
-        for shot in &state.spaceship_shots {
+        for shot in &self.spaceship_shots {
             pencil.draw_char('|', *shot);
         }
-    });
-}
\ No newline at end of file
+    }
+
+    // The best score of the players
+    fn score(&self) -> usize {
+        self.player.score.max(self.waiting.as_ref().map_or(0, |player| player.score))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.player.lives == 0 && self.waiting.as_ref().is_none_or(|player| player.lives == 0)
+    }
+
+    fn mode(&self) -> String {
+        if self.is_two_players() {
+            "2 players".to_string()
+        } else {
+            "classic".to_string()
+        }
+    }
+
+    // The score of every player, and who won a two-player game.
+    fn summary(&self) -> Vec<String> {
+        let Some(waiting) = &self.waiting else {
+            let (wave, score) = (self.player.wave, self.player.score);
+            return vec![format!("You lose :(  -  wave: {}  -  score: {}", wave, score)];
+        };
+        let mut lines = Vec::new();
+        let mut scores = Vec::new();
+        for number in 1..=2 {
+            let player = if number == self.player_number { &self.player } else { waiting };
+            lines.push(format!("player {}: {} points, wave {}", number, player.score, player.wave));
+            scores.push(player.score);
+        }
+        lines.push(match scores[0].cmp(&scores[1]) {
+            std::cmp::Ordering::Greater => "player 1 wins!".to_string(),
+            std::cmp::Ordering::Less => "player 2 wins!".to_string(),
+            std::cmp::Ordering::Equal => "it's a tie!".to_string(),
+        });
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::aliens::AlienKind;
+    use super::*;
+
+    fn one_player() -> GameState {
+        let mut game = GameState::new(DIMENSION, 1);
+        game.handle_action_event(&ActionEvent::Pressed(Action::Fire));
+        game
+    }
+
+    fn two_players() -> GameState {
+        let mut game = GameState::new(DIMENSION, 1);
+        game.handle_action_event(&ActionEvent::Pressed(Action::MoveRight));
+        game.handle_action_event(&ActionEvent::Pressed(Action::Fire));
+        game.turn_pause = 0;
+        game
+    }
+
+    fn hit_spaceship(game: &mut GameState) {
+        game.aliens_shots = vec![AlienShot::new(game.spaceship, ShotKind::Straight)];
+        game.update();
+    }
+
+    #[test]
+    fn bunkers_stop_the_shots_of_the_spaceship() {
+        let mut game = one_player();
+        game.spaceship_shots = vec![Vec2::xy(5, game.spaceship.y - 1)];
+        for _ in 0..5 {
+            game.update();
+        }
+        assert!(game.spaceship_shots.is_empty());
+        let chipped: Vec<Vec2> = game
+            .player
+            .bunkers
+            .iter()
+            .filter(|cell| cell.glyph() == ':')
+            .map(|cell| cell.position)
+            .collect();
+        assert_eq!(chipped, vec![Vec2::xy(5, game.spaceship.y - 4)]);
+    }
+
+    #[test]
+    fn bunkers_stop_the_shots_of_the_aliens() {
+        let mut game = one_player();
+        game.aliens_shots = vec![AlienShot::new(Vec2::xy(5, 10), ShotKind::Straight)];
+        for _ in 0..(ALIENS_SHOTS_PERIOD + 1) * 8 {
+            game.update();
+        }
+        assert!(!game.aliens_shots.iter().any(|shot| shot.position == Vec2::xy(5, game.spaceship.y)));
+        assert!(game.player.bunkers.iter().any(|cell| cell.position.x == 5 && cell.glyph() == ':'));
+    }
+
+    #[test]
+    fn aliens_higher_up_are_worth_more() {
+        let mut game = one_player();
+        let scores: Vec<usize> = (0..5).map(|row| AlienKind::of_row(row).score()).collect();
+        assert_eq!(scores, vec![30, 20, 20, 10, 10]);
+
+        game.player.aliens = vec![
+            Alien::new(Vec2::xy(10, 4), AlienKind::Squid),
+            Alien::new(Vec2::xy(20, 4), AlienKind::Octopus),
+            Alien::new(Vec2::xy(30, 4), AlienKind::Crab),
+        ];
+        game.spaceship_shots = vec![Vec2::xy(10, 5), Vec2::xy(20, 5)];
+        game.update();
+        assert_eq!(game.player.aliens.len(), 1);
+        assert_eq!(game.player.score, 40);
+    }
+
+    #[test]
+    fn shooting_the_mystery_ship_shows_its_bonus_for_a_while() {
+        let mut game = one_player();
+        game.ufo = Some(Ufo { position: Vec2::xy(20, 1), direction: 1, score: 150 });
+        game.spaceship_shots = vec![Vec2::xy(20, 2)];
+        game.update();
+        assert!(game.ufo.is_none());
+        assert_eq!(game.player.score, 150);
+        assert_eq!(game.ufo_bonus, Some((Vec2::xy(20, 1), 150, 1 + UFO_BONUS_TICKS)));
+        assert!(game.next_ufo_tick > UFO_PERIOD.start);
+
+        for _ in 0..UFO_BONUS_TICKS {
+            game.update();
+        }
+        assert!(game.ufo_bonus.is_none());
+    }
+
+    #[test]
+    fn aliens_speed_up_as_they_fall_and_every_wave() {
+        let mut game = one_player();
+        assert_eq!(game.player.aliens_movement_period(), ALIENS_MOVEMENT_PERIOD);
+        game.player.aliens.truncate(game.player.wave_size / 2);
+        assert_eq!(game.player.aliens_movement_period(), ALIENS_MOVEMENT_PERIOD / 2);
+        game.player.aliens.truncate(1);
+        assert_eq!(game.player.aliens_movement_period(), 1);
+
+        game.player.start_wave(DIMENSION);
+        assert_eq!(game.player.aliens_movement_period(), ALIENS_MOVEMENT_PERIOD - WAVE_SPEED_UP);
+        for _ in 0..20 {
+            game.player.start_wave(DIMENSION);
+        }
+        assert_eq!(game.player.aliens_movement_period(), MIN_WAVE_MOVEMENT_PERIOD);
+    }
+
+    #[test]
+    fn shooting_the_last_alien_brings_a_lower_wave() {
+        let mut game = one_player();
+        game.player.aliens = vec![Alien::new(Vec2::xy(10, 4), AlienKind::Octopus)];
+        game.player.bunkers.clear();
+        game.spaceship_shots = vec![Vec2::xy(10, 5)];
+        game.update();
+        assert_eq!(game.player.wave, 2);
+        assert!(!game.is_finished());
+        assert_eq!(game.player.aliens.len(), game.player.wave_size);
+        assert_eq!(game.player.aliens.iter().map(|alien| alien.position.y).min(), Some(ALIENS_TOP + 1));
+        assert!(!game.player.bunkers.is_empty());
+    }
+
+    #[test]
+    fn extra_lives_every_few_points() {
+        let mut game = one_player();
+        game.player.score = EXTRA_LIFE_SCORE * 2;
+        game.update();
+        assert_eq!(game.player.lives, 5);
+        assert_eq!(game.player.next_extra_life, EXTRA_LIFE_SCORE * 3);
+    }
+
+    #[test]
+    fn only_the_lowest_alien_of_a_column_fires() {
+        let mut game = one_player();
+        let column = vec![
+            Alien::new(Vec2::xy(10, 3), AlienKind::Crab),
+            Alien::new(Vec2::xy(10, 5), AlienKind::Octopus),
+        ];
+        let mut fired = false;
+        for _ in 0..3000 {
+            game.player.aliens = column.clone();
+            game.update();
+            assert!(game.aliens_shots.iter().all(|shot| shot.position.y > 5));
+            fired |= !game.aliens_shots.is_empty();
+        }
+        assert!(fired);
+    }
+
+    #[test]
+    fn aliens_have_three_shots_at_most_on_the_field() {
+        let mut game = one_player();
+        let mut most = 0;
+        for _ in 0..600 {
+            game.update();
+            most = most.max(game.aliens_shots.len());
+        }
+        assert_eq!(most, MAX_ALIENS_SHOTS);
+    }
+
+    #[test]
+    fn the_setup_screen_chooses_the_number_of_players() {
+        let mut game = GameState::new(DIMENSION, 1);
+        game.update();
+        assert_eq!(game.tick, 0);
+        game.handle_action_event(&ActionEvent::Pressed(Action::MoveLeft));
+        assert_eq!(game.setup, Some(2));
+        game.handle_action_event(&ActionEvent::Pressed(Action::Fire));
+        assert_eq!(game.setup, None);
+        assert!(game.is_two_players());
+        assert_eq!(game.turn_pause, TURN_PAUSE_TICKS);
+        assert!(!one_player().is_two_players());
+    }
+
+    #[test]
+    fn losing_a_life_hands_over_to_the_other_player() {
+        let mut game = two_players();
+        game.player.score = 100;
+        game.player.aliens.truncate(10);
+        hit_spaceship(&mut game);
+        assert_eq!(game.player_number, 2);
+        assert_eq!(game.turn_pause, TURN_PAUSE_TICKS);
+        assert!(game.aliens_shots.is_empty());
+        assert_eq!((game.player.score, game.player.lives), (0, LIVES));
+        assert_eq!(game.player.aliens.len(), game.player.wave_size);
+
+        let first = game.player_state(1).unwrap();
+        assert_eq!((first.score, first.lives, first.aliens.len()), (100, LIVES - 1, 10));
+
+        game.turn_pause = 0;
+        hit_spaceship(&mut game);
+        assert_eq!(game.player_number, 1);
+        assert_eq!(game.player.score, 100);
+    }
+
+    #[test]
+    fn a_player_out_of_lives_is_skipped() {
+        let mut game = two_players();
+        game.waiting.as_mut().unwrap().lives = 0;
+        hit_spaceship(&mut game);
+        assert_eq!(game.player_number, 1);
+        assert_eq!(game.player.lives, LIVES - 1);
+
+        game.player.lives = 1;
+        hit_spaceship(&mut game);
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn the_summary_names_the_winner() {
+        let mut game = two_players();
+        game.player.score = 300;
+        game.player.lives = 0;
+        let waiting = game.waiting.as_mut().unwrap();
+        waiting.score = 500;
+        waiting.lives = 0;
+        let summary = game.summary();
+        assert_eq!(summary, ["player 1: 300 points, wave 1", "player 2: 500 points, wave 1", "player 2 wins!"]);
+    }
+}
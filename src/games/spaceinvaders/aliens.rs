@@ -0,0 +1,258 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use ruscii::spatial::Vec2;
+use ruscii::terminal::Color;
+
+use crate::collision::{self, Rect};
+use crate::physics::Vec2f;
+
+// The bonus points a mystery ship can be worth
+const UFO_SCORES: [usize; 4] = [50, 100, 150, 300];
+// How many ticks the mystery ship takes to move by one cell
+const UFO_MOVEMENT_PERIOD: usize = 2;
+
+// The kinds of aliens, one per row of the formation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlienKind {
+    // The top row
+    Squid,
+    // The two middle rows
+    Crab,
+    // The two bottom rows
+    Octopus,
+}
+
+impl AlienKind {
+    // The kind of the aliens of a row, counted from the top of the formation.
+    pub fn of_row(row: usize) -> AlienKind {
+        match row {
+            0 => AlienKind::Squid,
+            1..=2 => AlienKind::Crab,
+            _ => AlienKind::Octopus,
+        }
+    }
+
+    pub fn score(&self) -> usize {
+        match self {
+            AlienKind::Squid => 30,
+            AlienKind::Crab => 20,
+            AlienKind::Octopus => 10,
+        }
+    }
+
+    // What the alien looks like, for both frames of its animation.
+    pub fn glyph(&self, frame: bool) -> char {
+        match (self, frame) {
+            (AlienKind::Squid, false) => 'Y',
+            (AlienKind::Squid, true) => 'V',
+            (AlienKind::Crab, false) => 'H',
+            (AlienKind::Crab, true) => 'X',
+            (AlienKind::Octopus, false) => 'W',
+            (AlienKind::Octopus, true) => 'M',
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            AlienKind::Squid => Color::Magenta,
+            AlienKind::Crab => Color::Cyan,
+            AlienKind::Octopus => Color::Green,
+        }
+    }
+}
+
+/*
+    Alien defines one alien of the formation.
+    - its current position
+    - what kind of alien it is
+*/
+#[derive(Clone, Debug)]
+pub struct Alien {
+    pub position: Vec2,
+    pub kind: AlienKind,
+}
+
+impl Alien {
+    pub fn new(position: Vec2, kind: AlienKind) -> Alien {
+        Alien { position, kind }
+    }
+}
+
+// The five rows of aliens of a wave, the top one at row `top` of a field
+// `width` cells wide.
+pub fn formation(top: i32, width: i32) -> Vec<Alien> {
+    let mut aliens = Vec::new();
+    for row in 0..5 {
+        for x in 5..width - 5 {
+            if x % 2 != 0 {
+                aliens.push(Alien::new(Vec2::xy(x, top + row as i32), AlienKind::of_row(row)));
+            }
+        }
+    }
+    aliens
+}
+
+// Whether an alien is the lowest of its column, the only one that can fire
+// without hitting the others.
+pub fn is_bottom_of_column(alien: &Alien, aliens: &[Alien]) -> bool {
+    !aliens
+        .iter()
+        .any(|other| other.position.x == alien.position.x && other.position.y > alien.position.y)
+}
+
+// The ways the shots of the aliens move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShotKind {
+    // Falls straight down
+    Straight,
+    // Swerves left and right as it falls
+    ZigZag,
+    // Heads for where the spaceship was when it was fired, given by its column
+    Targeted(i32),
+}
+
+impl ShotKind {
+    // Picks the kind of a new shot aimed at the spaceship in column
+    // `target`: half of them fall straight.
+    pub fn random(target: i32, rng: &mut StdRng) -> ShotKind {
+        match rng.gen_range(0..4) {
+            0 => ShotKind::ZigZag,
+            1 => ShotKind::Targeted(target),
+            _ => ShotKind::Straight,
+        }
+    }
+}
+
+/*
+    AlienShot defines a shot fired by an alien.
+    - its current position
+    - how it moves
+*/
+#[derive(Clone, Debug)]
+pub struct AlienShot {
+    pub position: Vec2,
+    pub kind: ShotKind,
+}
+
+impl AlienShot {
+    pub fn new(position: Vec2, kind: ShotKind) -> AlienShot {
+        AlienShot { position, kind }
+    }
+
+    // Where the shot goes on its next step: one row down, and maybe one
+    // column to the side.
+    pub fn motion(&self) -> Vec2 {
+        let dx = match self.kind {
+            ShotKind::Straight => 0,
+            ShotKind::ZigZag if self.position.y % 2 == 0 => 1,
+            ShotKind::ZigZag => -1,
+            ShotKind::Targeted(target) => (target - self.position.x).signum(),
+        };
+        Vec2::xy(dx, 1)
+    }
+
+    pub fn glyph(&self) -> char {
+        match self.kind {
+            ShotKind::Straight => '|',
+            ShotKind::ZigZag if self.position.y % 2 == 0 => '\\',
+            ShotKind::ZigZag => '/',
+            ShotKind::Targeted(_) => '!',
+        }
+    }
+}
+
+/*
+    Ufo defines the mystery ship crossing the top of the field.
+    - the position of its middle
+    - which direction it is going in
+    - the points it is worth
+*/
+#[derive(Clone, Debug)]
+pub struct Ufo {
+    pub position: Vec2,
+    pub direction: i32,
+    pub score: usize,
+}
+
+impl Ufo {
+    pub const GLYPH: &'static str = "<=>";
+
+    // A mystery ship entering the row `y` of a field `width` cells wide, from
+    // either side, worth a random bonus. It is drawn a cell either side of
+    // its middle, all of it inside the field.
+    pub fn new(width: i32, y: i32, rng: &mut StdRng) -> Ufo {
+        let from_left: bool = rng.gen();
+        Ufo {
+            position: Vec2::xy(if from_left { 1 } else { width - 2 }, y),
+            direction: if from_left { 1 } else { -1 },
+            score: UFO_SCORES[rng.gen_range(0..UFO_SCORES.len())],
+        }
+    }
+
+    // Whether a shot moving from `start` along `motion` hits the ship.
+    pub fn is_hit(&self, start: Vec2f, motion: Vec2f) -> bool {
+        let rect = Rect::from_cells(self.position - Vec2::x(1), Vec2::xy(3, 1));
+        rect.contains(start) || collision::sweep_point(start, motion, &rect).is_some()
+    }
+
+    // Moves the ship along every few ticks. Returns false once it has left
+    // the field.
+    pub fn fly(&mut self, tick: usize, width: i32) -> bool {
+        if tick.is_multiple_of(UFO_MOVEMENT_PERIOD) {
+            self.position.x += self.direction;
+        }
+        (1..=width - 2).contains(&self.position.x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn only_the_bottom_row_of_a_formation_can_fire() {
+        let aliens = formation(2, 50);
+        let shooters: Vec<&Alien> = aliens.iter().filter(|alien| is_bottom_of_column(alien, &aliens)).collect();
+        assert_eq!(shooters.len(), 20);
+        assert!(shooters.iter().all(|alien| alien.position.y == 6));
+    }
+
+    #[test]
+    fn shots_move_by_kind() {
+        let straight = AlienShot::new(Vec2::xy(10, 4), ShotKind::Straight);
+        assert_eq!(straight.motion(), Vec2::xy(0, 1));
+
+        let mut zigzag = AlienShot::new(Vec2::xy(10, 4), ShotKind::ZigZag);
+        let mut columns = Vec::new();
+        for _ in 0..4 {
+            zigzag.position += zigzag.motion();
+            columns.push(zigzag.position.x);
+        }
+        assert_eq!(columns, vec![11, 10, 11, 10]);
+
+        let mut targeted = AlienShot::new(Vec2::xy(10, 4), ShotKind::Targeted(8));
+        for _ in 0..4 {
+            targeted.position += targeted.motion();
+        }
+        assert_eq!(targeted.position, Vec2::xy(8, 8));
+    }
+
+    #[test]
+    fn the_mystery_ship_stays_inside_the_field() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..10 {
+            let mut ufo = Ufo::new(50, 1, &mut rng);
+            let mut tick = 0;
+            loop {
+                let left = ufo.position.x - 1;
+                assert!(left >= 0 && left + Ufo::GLYPH.len() as i32 <= 50);
+                tick += 1;
+                if !ufo.fly(tick, 50) {
+                    break;
+                }
+            }
+        }
+    }
+}
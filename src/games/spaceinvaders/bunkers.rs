@@ -0,0 +1,126 @@
+use ruscii::spatial::Vec2;
+
+use crate::collision::{self, Rect};
+use crate::physics::Vec2f;
+
+// How many hits a piece of bunker takes before it is gone
+const BUNKER_HITS: u32 = 2;
+// The shape of a bunker, from its top left corner
+const BUNKER_SHAPE: [&str; 3] = [
+    " #### ",
+    "######",
+    "##  ##",
+];
+// How many rows above the spaceship the top of the bunkers is
+const BUNKER_HEIGHT: i32 = 5;
+
+// When something moving from `start` along `motion` goes into the given
+// cell, as a fraction of the motion (0 if it already is in it).
+pub fn hit_time(cell: Vec2, start: Vec2f, motion: Vec2f) -> Option<f32> {
+    let rect = Rect::from_cells(cell, Vec2::xy(1, 1));
+    if rect.contains(start) {
+        return Some(0.0);
+    }
+    collision::sweep_point(start, motion, &rect).map(|hit| hit.time)
+}
+
+/*
+    BunkerCell defines one piece of a bunker.
+    - its position
+    - how many more hits it takes to destroy it
+*/
+#[derive(Clone, Debug)]
+pub struct BunkerCell {
+    pub position: Vec2,
+    pub hits_left: u32,
+}
+
+impl BunkerCell {
+    // What the piece looks like, as it wears away.
+    pub fn glyph(&self) -> char {
+        if self.hits_left >= BUNKER_HITS { '#' } else { ':' }
+    }
+}
+
+// The four bunkers spread between the spaceship at row `spaceship_y` and
+// the aliens, on a field `width` cells wide.
+pub fn build_bunkers(width: i32, spaceship_y: i32) -> Vec<BunkerCell> {
+    let bunker_width = BUNKER_SHAPE[0].len() as i32;
+    let top = spaceship_y - BUNKER_HEIGHT;
+    let mut cells = Vec::new();
+    for bunker in 0..4 {
+        let left = width * (bunker * 2 + 1) / 8 - bunker_width / 2;
+        for (y, row) in BUNKER_SHAPE.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == '#' {
+                    cells.push(BunkerCell {
+                        position: Vec2::xy(left + x as i32, top + y as i32),
+                        hits_left: BUNKER_HITS,
+                    });
+                }
+            }
+        }
+    }
+    cells
+}
+
+// Chips away the first piece of bunker in the way of a shot moving from
+// `start` along `motion`. Returns whether the shot was stopped.
+pub fn chip(bunkers: &mut Vec<BunkerCell>, start: Vec2f, motion: Vec2f) -> bool {
+    let mut first: Option<(usize, f32)> = None;
+    for (index, cell) in bunkers.iter().enumerate() {
+        if let Some(time) = hit_time(cell.position, start, motion) {
+            if !first.is_some_and(|(_, first_time)| first_time <= time) {
+                first = Some((index, time));
+            }
+        }
+    }
+    let Some((index, _)) = first else {
+        return false;
+    };
+    bunkers[index].hits_left -= 1;
+    if bunkers[index].hits_left == 0 {
+        bunkers.remove(index);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(x: i32, y: i32) -> BunkerCell {
+        BunkerCell { position: Vec2::xy(x, y), hits_left: BUNKER_HITS }
+    }
+
+    #[test]
+    fn four_bunkers_above_the_spaceship() {
+        let bunkers = build_bunkers(50, 20);
+        assert_eq!(bunkers.len(), 4 * 14);
+        assert!(bunkers.iter().all(|cell| (15..18).contains(&cell.position.y)));
+        assert!(bunkers.iter().all(|cell| (0..50).contains(&cell.position.x)));
+    }
+
+    #[test]
+    fn shots_wear_away_the_first_piece_in_their_way() {
+        let mut bunkers = vec![cell(3, 5), cell(3, 4)];
+        let start = Vec2f::xy(3.0, 6.0);
+        let up = Vec2f::xy(0.0, -1.0);
+        assert!(chip(&mut bunkers, start, up));
+        assert_eq!(bunkers[0].hits_left, BUNKER_HITS - 1);
+        assert_eq!(bunkers[0].glyph(), ':');
+        assert_eq!(bunkers[1].hits_left, BUNKER_HITS);
+
+        assert!(chip(&mut bunkers, start, up));
+        assert_eq!(bunkers.len(), 1);
+        assert_eq!(bunkers[0].position, Vec2::xy(3, 4));
+    }
+
+    #[test]
+    fn shots_next_to_a_bunker_go_on() {
+        let mut bunkers = vec![cell(3, 5)];
+        assert!(!chip(&mut bunkers, Vec2f::xy(4.0, 6.0), Vec2f::xy(0.0, -1.0)));
+        assert!(!chip(&mut bunkers, Vec2f::xy(3.0, 8.0), Vec2f::xy(0.0, -1.0)));
+        assert_eq!(bunkers[0].hits_left, BUNKER_HITS);
+    }
+}
@@ -0,0 +1,210 @@
+use ruscii::spatial::Vec2;
+use ruscii::terminal::{Canvas, Color, VisualElement};
+
+use crate::game::{self, Game, Input};
+
+/*
+    Frame is a copy of everything that was drawn on screen for one step.
+    - the size of the screen
+    - the character and colors of every cell, row by row
+*/
+#[derive(Clone)]
+pub struct Frame {
+    pub dimension: Vec2,
+    cells: Vec<VisualElement>,
+}
+
+impl Frame {
+    fn new(canvas: &Canvas) -> Frame {
+        let dimension = canvas.dimension();
+        let mut cells = Vec::with_capacity((dimension.x * dimension.y) as usize);
+        for y in 0..dimension.y {
+            for x in 0..dimension.x {
+                cells.push(*canvas.elem(Vec2::xy(x, y)).unwrap());
+            }
+        }
+        Frame { dimension, cells }
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<&VisualElement> {
+        if position.x < 0 || position.y < 0
+            || position.x >= self.dimension.x || position.y >= self.dimension.y {
+            return None;
+        }
+        self.cells.get((position.y * self.dimension.x + position.x) as usize)
+    }
+
+    pub fn char_at(&self, position: Vec2) -> Option<char> {
+        self.cell(position).map(|cell| cell.value)
+    }
+
+    pub fn foreground_at(&self, position: Vec2) -> Option<Color> {
+        self.cell(position).map(|cell| cell.foreground)
+    }
+
+    pub fn background_at(&self, position: Vec2) -> Option<Color> {
+        self.cell(position).map(|cell| cell.background)
+    }
+
+    // Finds the first cell (row by row) that holds the given character.
+    pub fn find(&self, value: char) -> Option<Vec2> {
+        self.cells
+            .iter()
+            .position(|cell| cell.value == value)
+            .map(|index| Vec2::xy(index as i32 % self.dimension.x, index as i32 / self.dimension.x))
+    }
+
+    // The characters of the frame, one string per row.
+    pub fn lines(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.dimension.x as usize)
+            .map(|row| row.iter().map(|cell| cell.value).collect())
+            .collect()
+    }
+}
+
+/*
+    Headless plays a game without a terminal: every step is drawn on an
+    in-memory canvas of the given window size, so that what would be on
+    screen can be inspected from tests.
+*/
+pub struct Headless<G: Game> {
+    pub game: G,
+    canvas: Canvas,
+}
+
+impl<G: Game> Headless<G> {
+    pub fn new(win_size: Vec2, seed: u64) -> Headless<G> {
+        Headless {
+            game: G::new(win_size, seed),
+            canvas: Canvas::new(win_size, &VisualElement::default()),
+        }
+    }
+
+    // Advances the game by one step with the given input. The window is
+    // resized first if the input says so.
+    pub fn step(&mut self, input: &Input) -> Frame {
+        if let Some(win_size) = input.win_size {
+            self.canvas = Canvas::new(win_size, &VisualElement::default());
+        }
+        game::step(&mut self.game, input);
+        self.frame()
+    }
+
+    // Advances the game by `steps` steps without any input.
+    pub fn run(&mut self, steps: usize) -> Frame {
+        for _ in 0..steps {
+            game::step(&mut self.game, &Input::default());
+        }
+        self.frame()
+    }
+
+    // Draws the game as it currently is.
+    pub fn frame(&mut self) -> Frame {
+        self.canvas.clear();
+        game::draw(&self.game, &mut self.canvas);
+        Frame::new(&self.canvas)
+    }
+
+    // Where the top left of the playfield is drawn on the screen.
+    pub fn origin(&self) -> Vec2 {
+        (self.canvas.dimension() - self.game.dimension()) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::{Action, ActionEvent};
+    use crate::games::spaceinvaders::aliens::{AlienShot, ShotKind};
+    use crate::games::{breakout, pong, spaceinvaders};
+    use crate::physics::Vec2f;
+
+    const WIN_SIZE: Vec2 = Vec2 { x: 100, y: 40 };
+
+    // A match of Pong against the computer, past the setup screen.
+    fn pong(seed: u64) -> Headless<pong::GameState> {
+        let mut headless = Headless::new(WIN_SIZE, seed);
+        let start = Input {
+            events: vec![ActionEvent::Pressed(Action::Fire)],
+            ..Input::default()
+        };
+        headless.step(&start);
+        headless
+    }
+
+    #[test]
+    fn pong_ball_after_30_ticks() {
+        let mut headless = pong(42);
+        let frame = headless.run(30);
+        let ball = headless.origin() + headless.game.ball_position.to_cell();
+        assert_eq!(ball, Vec2::xy(67, 4));
+        assert_eq!(frame.char_at(ball), Some('o'));
+    }
+
+    #[test]
+    fn breakout_ball_after_3_steps() {
+        let mut headless = Headless::<breakout::GameState>::new(WIN_SIZE, 42);
+        headless.step(&Input { events: vec![ActionEvent::Pressed(Action::Fire)], ..Input::default() });
+        headless.game.balls[0].position = Vec2f::xy(50.0, 30.0);
+        headless.game.balls[0].velocity = Vec2f::xy(1.0, -1.0);
+        let frame = headless.run(3);
+        let ball = headless.origin() + Vec2::xy(53, 27);
+        assert_eq!(frame.char_at(ball), Some('0'));
+        assert_eq!(frame.foreground_at(ball), Some(Color::Yellow));
+    }
+
+    #[test]
+    fn keys_down_move_the_bouncer() {
+        let mut headless = Headless::<breakout::GameState>::new(WIN_SIZE, 42);
+        let start = headless.game.bouncer.position;
+        let input = Input { actions_down: vec![Action::MoveRight], ..Input::default() };
+        let frame = headless.step(&input);
+        let bouncer = headless.origin() + start + Vec2::x(2);
+        assert_eq!(headless.game.bouncer.position, start + Vec2::x(2));
+        assert_eq!(frame.char_at(bouncer), Some('╔'));
+    }
+
+    #[test]
+    fn lines_cover_the_whole_window() {
+        let mut headless = Headless::<breakout::GameState>::new(WIN_SIZE, 42);
+        let lines = headless.frame().lines();
+        assert_eq!(lines.len(), WIN_SIZE.y as usize);
+        assert!(lines.iter().all(|line| line.chars().count() == WIN_SIZE.x as usize));
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_frames() {
+        let (mut first, mut second) = (pong(7), pong(7));
+        for _ in 0..10 {
+            assert_eq!(first.run(10).lines(), second.run(10).lines());
+        }
+    }
+
+    #[test]
+    fn resizing_redraws_at_the_new_size() {
+        let mut headless = Headless::<breakout::GameState>::new(WIN_SIZE, 42);
+        let input = Input { win_size: Some(Vec2::xy(60, 30)), ..Input::default() };
+        let frame = headless.step(&input);
+        assert_eq!(frame.dimension, Vec2::xy(60, 30));
+        assert_eq!(headless.game.dimension(), Vec2::xy(60, 30));
+        assert_eq!(headless.game.bouncer.position.y, 28);
+    }
+
+    #[test]
+    fn space_invaders_players_take_turns() {
+        let mut headless = Headless::<spaceinvaders::GameState>::new(WIN_SIZE, 42);
+        let press = |action| Input { events: vec![ActionEvent::Pressed(action)], ..Input::default() };
+        headless.step(&press(Action::MoveRight));
+        let frame = headless.step(&press(Action::Fire));
+        assert!(frame.lines().iter().any(|line| line.contains("PLAYER 1")));
+
+        let frame = headless.run(60);
+        assert!(frame.lines().iter().any(|line| line.contains(">1UP: 0  2UP: 0")));
+        let spaceship = headless.game.spaceship;
+        headless.game.aliens_shots = vec![AlienShot::new(spaceship, ShotKind::Straight)];
+        let frame = headless.run(1);
+        assert!(frame.lines().iter().any(|line| line.contains("PLAYER 2")));
+        assert!(frame.lines().iter().any(|line| line.contains(" 1UP: 0 >2UP: 0")));
+    }
+}
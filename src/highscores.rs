@@ -0,0 +1,297 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ruscii::drawing::{Pencil, RectCharset};
+use ruscii::spatial::Vec2;
+use ruscii::terminal::Color;
+
+use crate::paths;
+
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const INITIALS_LEN: usize = 3;
+
+const TABLE_WIDTH: i32 = 44;
+
+/*
+    HighScore defines one row of a high-score table.
+    - the initials of the player
+    - the score, the date it was made on, the seed and the game mode
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: usize,
+    pub date: String,
+    pub seed: u64,
+    pub mode: String,
+}
+
+impl HighScore {
+    pub fn new(initials: &str, score: usize, seed: u64, mode: &str) -> HighScore {
+        HighScore {
+            initials: initials.to_string(),
+            score,
+            date: today(),
+            seed,
+            mode: mode.to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<HighScore> {
+        let mut fields = line.split('\t');
+        let score = fields.next()?.parse().ok()?;
+        let initials = fields.next()?.to_string();
+        let date = fields.next()?.to_string();
+        let seed = fields.next()?.parse().ok()?;
+        let mode = fields.next()?.to_string();
+        Some(HighScore { initials, score, date, seed, mode })
+    }
+
+    fn to_line(&self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}", self.score, self.initials, self.date, self.seed, self.mode)
+    }
+}
+
+/*
+    HighScores is the table of the best scores of one game, best first.
+    It is kept in the data directory, one file per game.
+*/
+#[derive(Clone, Debug)]
+pub struct HighScores {
+    pub game: String,
+    pub scores: Vec<HighScore>,
+}
+
+impl HighScores {
+    fn path(game: &str) -> Option<PathBuf> {
+        let file_name = format!("{}.tsv", paths::slug(game));
+        paths::data_dir().map(|dir| dir.join("highscores").join(file_name))
+    }
+
+    // Loads the table of a game. A missing or unreadable file is an empty table.
+    pub fn load(game: &str) -> HighScores {
+        let scores = HighScores::path(game)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().filter_map(HighScore::parse).collect())
+            .unwrap_or_default();
+        HighScores {
+            game: game.to_string(),
+            scores,
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = HighScores::path(&self.game)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let lines: Vec<String> = self.scores.iter().map(HighScore::to_line).collect();
+        fs::write(path, lines.join("\n") + "\n")
+    }
+
+    // Whether the score is good enough to be added to the table.
+    pub fn qualifies(&self, score: usize) -> bool {
+        match self.scores.get(MAX_HIGH_SCORES - 1) {
+            Some(last) => score > last.score,
+            None => score > 0,
+        }
+    }
+
+    // Adds the score to the table, returning its rank if it made it in.
+    pub fn insert(&mut self, high_score: HighScore) -> Option<usize> {
+        let rank = self
+            .scores
+            .iter()
+            .position(|other| high_score.score > other.score)
+            .unwrap_or(self.scores.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.scores.insert(rank, high_score);
+        self.scores.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+// Today's date (UTC) as YYYY-MM-DD.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    // Convert the days since 1970-01-01 to a civil date
+    // (http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Draws a box of the given lines centered on `center`, hiding what is below.
+// The line at `highlight` (if any) is drawn in yellow.
+pub fn draw_panel(pencil: &mut Pencil, lines: &[String], highlight: Option<usize>, center: Vec2) {
+    let dimension = Vec2::xy(TABLE_WIDTH, lines.len() as i32 + 2);
+    let origin = center - dimension / 2;
+
+    pencil.set_foreground(Color::Grey);
+    pencil.draw_rect(&RectCharset::simple_round_lines(), origin, dimension);
+    for (index, line) in lines.iter().enumerate() {
+        let color = if Some(index) == highlight { Color::Yellow } else { Color::White };
+        pencil.set_foreground(color);
+        pencil.draw_text(
+            &format!(" {:<width$}", line, width = TABLE_WIDTH as usize - 3),
+            origin + Vec2::xy(1, index as i32 + 1),
+        );
+    }
+}
+
+// The lines a panel starts with to show the summary of a game, if there is
+// one: the summary and a blank line below it.
+pub fn summary_lines(summary: &[String]) -> Vec<String> {
+    let mut lines = summary.to_vec();
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+// Draws the table of high scores below the summary of the game, highlighting
+// the row at `highlight`, with the keys that can be pressed below it.
+pub fn draw_table(
+    pencil: &mut Pencil,
+    summary: &[String],
+    high_scores: &HighScores,
+    highlight: Option<usize>,
+    help: &str,
+    center: Vec2,
+) {
+    let mut lines = summary_lines(summary);
+    let top = lines.len();
+    lines.push(format!("{} - high scores", high_scores.game));
+    lines.push(String::new());
+    if high_scores.scores.is_empty() {
+        lines.push("no scores yet".to_string());
+    }
+    for (rank, high_score) in high_scores.scores.iter().enumerate() {
+        lines.push(format!(
+            "{:>2}. {:<3} {:>7}  {}  {}",
+            rank + 1,
+            high_score.initials,
+            high_score.score,
+            high_score.date,
+            high_score.mode
+        ));
+    }
+    lines.push(String::new());
+    lines.push(help.to_string());
+
+    draw_panel(pencil, &lines, highlight.map(|rank| top + rank + 2), center);
+}
+
+// Draws the prompt asking for the initials of a new high score, below the
+// summary of the game.
+pub fn draw_initials_prompt(
+    pencil: &mut Pencil,
+    summary: &[String],
+    score: usize,
+    initials: &str,
+    center: Vec2,
+) {
+    let mut lines = summary_lines(summary);
+    let top = lines.len();
+    lines.extend([
+        "NEW HIGH SCORE!".to_string(),
+        String::new(),
+        format!("score: {}", score),
+        format!("initials: {:_<width$}", initials, width = INITIALS_LEN),
+        String::new(),
+        "enter: save  -  esc: skip".to_string(),
+    ]);
+    draw_panel(pencil, &lines, Some(top), center);
+}
+
+#[cfg(test)]
+mod tests {
+    use ruscii::terminal::{Canvas, VisualElement};
+
+    use super::*;
+
+    fn table(scores: &[usize]) -> HighScores {
+        HighScores {
+            game: "Test".to_string(),
+            scores: scores.iter().map(|score| HighScore::new("ABC", *score, 0, "classic")).collect(),
+        }
+    }
+
+    #[test]
+    fn qualifies() {
+        assert!(!table(&[]).qualifies(0));
+        assert!(table(&[]).qualifies(1));
+        assert!(table(&[50, 10]).qualifies(5));
+
+        let full = table(&[100, 90, 80, 70, 60, 50, 40, 30, 20, 10]);
+        assert!(!full.qualifies(10));
+        assert!(full.qualifies(11));
+    }
+
+    #[test]
+    fn insert() {
+        let mut scores = table(&[100, 50]);
+        assert_eq!(scores.insert(HighScore::new("NEW", 75, 0, "classic")), Some(1));
+        // A tie goes below the score that was there first
+        assert_eq!(scores.insert(HighScore::new("TIE", 50, 0, "classic")), Some(3));
+        let ranked: Vec<(&str, usize)> =
+            scores.scores.iter().map(|score| (score.initials.as_str(), score.score)).collect();
+        assert_eq!(ranked, [("ABC", 100), ("NEW", 75), ("ABC", 50), ("TIE", 50)]);
+    }
+
+    #[test]
+    fn insert_into_full_table() {
+        let mut full = table(&[100, 90, 80, 70, 60, 50, 40, 30, 20, 10]);
+        assert_eq!(full.insert(HighScore::new("LOW", 10, 0, "classic")), None);
+        assert_eq!(full.insert(HighScore::new("TOP", 200, 0, "classic")), Some(0));
+        assert_eq!(full.scores.len(), MAX_HIGH_SCORES);
+        assert_eq!(full.scores.last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn line_round_trip() {
+        let high_score = HighScore::new("XYZ", 1234, 42, "2 players");
+        assert_eq!(HighScore::parse(&high_score.to_line()), Some(high_score));
+        assert_eq!(HighScore::parse("12\tABC"), None);
+    }
+
+    #[test]
+    fn summary_above_a_full_table_fits_the_smallest_window() {
+        // The smallest window of Space Invaders, the game with the longest summary
+        let size = Vec2::xy(52, 24);
+        let mut canvas = Canvas::new(size, &VisualElement::default());
+        let summary = ["player 1: 300 points, wave 2", "player 2: 500 points, wave 3", "player 2 wins!"]
+            .map(String::from);
+        let full = table(&[100, 90, 80, 70, 60, 50, 40, 30, 20, 10]);
+        draw_table(&mut Pencil::new(&mut canvas), &summary, &full, Some(3), "enter: continue", size / 2);
+
+        let rows: Vec<String> = (0..size.y)
+            .map(|y| (0..size.x).map(|x| canvas.elem(Vec2::xy(x, y)).unwrap().value).collect())
+            .collect();
+        let top = rows.iter().position(|row| row.contains("player 1: 300 points")).unwrap();
+        assert!(top > 0);
+        assert!(rows[top + 2].contains("player 2 wins!"));
+        assert!(rows[top + 4].contains("Test - high scores"));
+        assert!(rows[top + 9].contains(" 4. ABC      70"));
+        assert!(rows[..size.y as usize - 1].iter().any(|row| row.contains("enter: continue")));
+
+        let highlighted = canvas.elem(Vec2::xy(size.x / 2, top as i32 + 9)).unwrap();
+        assert_eq!(highlighted.foreground, Color::Yellow);
+    }
+}
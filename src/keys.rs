@@ -0,0 +1,39 @@
+use ruscii::keyboard::Key;
+
+// Every key that can be written to a file (like key bindings), by name.
+const KEY_NAMES: [(Key, &str); 57] = [
+    (Key::Esc, "Esc"), (Key::Space, "Space"), (Key::Enter, "Enter"),
+    (Key::Backspace, "Backspace"), (Key::Tab, "Tab"), (Key::Up, "Up"), (Key::Down, "Down"),
+    (Key::Left, "Left"), (Key::Right, "Right"), (Key::A, "A"), (Key::B, "B"), (Key::C, "C"),
+    (Key::D, "D"), (Key::E, "E"), (Key::F, "F"), (Key::G, "G"), (Key::H, "H"), (Key::I, "I"),
+    (Key::J, "J"), (Key::K, "K"), (Key::L, "L"), (Key::M, "M"), (Key::N, "N"), (Key::O, "O"),
+    (Key::P, "P"), (Key::Q, "Q"), (Key::R, "R"), (Key::S, "S"), (Key::T, "T"), (Key::U, "U"),
+    (Key::V, "V"), (Key::W, "W"), (Key::X, "X"), (Key::Y, "Y"), (Key::Z, "Z"),
+    (Key::Num0, "Num0"), (Key::Num1, "Num1"), (Key::Num2, "Num2"), (Key::Num3, "Num3"),
+    (Key::Num4, "Num4"), (Key::Num5, "Num5"), (Key::Num6, "Num6"), (Key::Num7, "Num7"),
+    (Key::Num8, "Num8"), (Key::Num9, "Num9"), (Key::F1, "F1"), (Key::F2, "F2"), (Key::F3, "F3"),
+    (Key::F4, "F4"), (Key::F5, "F5"), (Key::F6, "F6"), (Key::F7, "F7"), (Key::F8, "F8"),
+    (Key::F9, "F9"), (Key::F10, "F10"), (Key::F11, "F11"), (Key::F12, "F12"),
+];
+
+pub fn key_name(key: Key) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(named_key, _)| *named_key == key)
+        .map(|(_, name)| *name)
+}
+
+// The letter typed with a key (A to Z), if it is one.
+pub fn key_letter(key: Key) -> Option<char> {
+    key_name(key)
+        .filter(|name| name.len() == 1)
+        .and_then(|name| name.chars().next())
+        .filter(char::is_ascii_alphabetic)
+}
+
+pub fn key_from_name(name: &str) -> Option<Key> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, key_name)| key_name.eq_ignore_ascii_case(name))
+        .map(|(key, _)| *key)
+}
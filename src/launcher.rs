@@ -0,0 +1,126 @@
+use ruscii::app::{App, State};
+use ruscii::drawing::{Pencil, RectCharset};
+use ruscii::keyboard::{Key, KeyEvent};
+use ruscii::spatial::Vec2;
+use ruscii::terminal::{Color, Style, Window};
+
+use crate::games::GameEntry;
+use crate::highscores::{self, HighScores};
+
+const TITLE: &str = "R U S T I C A D E";
+const HELP: &str = "up/down: select - enter: play - h: high scores - q: quit";
+
+/*
+    MenuState defines the state of the game-selection menu.
+    - the games that can be picked
+    - which entry is currently highlighted
+    - the entry that was picked (if any)
+    - the high scores being looked at (if any)
+*/
+struct MenuState<'a> {
+    pub games: &'a [GameEntry],
+    pub selected: usize,
+    pub picked: Option<usize>,
+    pub high_scores: Option<HighScores>,
+}
+
+impl<'a> MenuState<'a> {
+    pub fn new(games: &'a [GameEntry], selected: usize) -> MenuState<'a> {
+        MenuState {
+            games,
+            selected: selected.min(games.len() - 1),
+            picked: None,
+            high_scores: None,
+        }
+    }
+
+    pub fn toggle_high_scores(&mut self) {
+        self.high_scores = match self.high_scores {
+            Some(_) => None,
+            None => Some(HighScores::load(self.games[self.selected].name)),
+        };
+    }
+
+    pub fn move_selection(&mut self, direction: i32) {
+        let len = self.games.len() as i32;
+        self.selected = (self.selected as i32 + direction).rem_euclid(len) as usize;
+    }
+
+    pub fn pick(&mut self) {
+        self.picked = Some(self.selected);
+    }
+}
+
+// Shows the menu until the player either picks a game or quits.
+// Returns the index of the picked game in `games`, or None on quit.
+pub fn select_game(games: &[GameEntry], selected: usize) -> Option<usize> {
+    let mut app = App::default();
+    let mut state = MenuState::new(games, selected);
+
+    app.run(|app_state: &mut State, window: &mut Window| {
+        for key_event in app_state.keyboard().last_key_events() {
+            // Any of these closes the high scores instead of acting on the menu
+            if state.high_scores.is_some() {
+                if let KeyEvent::Pressed(Key::Esc | Key::Q | Key::Enter | Key::H) = key_event {
+                    state.toggle_high_scores();
+                }
+                continue;
+            }
+
+            match key_event {
+                KeyEvent::Pressed(Key::H) => state.toggle_high_scores(),
+                KeyEvent::Pressed(Key::Esc) | KeyEvent::Pressed(Key::Q) => app_state.stop(),
+                KeyEvent::Pressed(Key::Up) | KeyEvent::Pressed(Key::W) | KeyEvent::Pressed(Key::K) => {
+                    state.move_selection(-1)
+                }
+                KeyEvent::Pressed(Key::Down) | KeyEvent::Pressed(Key::S) | KeyEvent::Pressed(Key::J) => {
+                    state.move_selection(1)
+                }
+                KeyEvent::Pressed(Key::Enter) | KeyEvent::Pressed(Key::Space) => {
+                    state.pick();
+                    app_state.stop();
+                }
+                _ => (),
+            }
+        }
+
+        let win_size = window.size();
+        let menu_size = Vec2::xy(HELP.len() as i32 + 4, state.games.len() as i32 + 6);
+        let mut pencil = Pencil::new(window.canvas_mut());
+
+        // Draw the frame of the menu in the middle of the window
+        pencil.set_origin((win_size - menu_size) / 2);
+        pencil.set_foreground(Color::Grey);
+        pencil.draw_rect(&RectCharset::double_lines(), Vec2::zero(), menu_size);
+
+        // Draw the title
+        pencil.set_foreground(Color::Red);
+        pencil.set_style(Style::Bold);
+        pencil.draw_text(TITLE, Vec2::xy((menu_size.x - TITLE.len() as i32) / 2, 1));
+        pencil.set_style(Style::Plain);
+
+        // Draw the games, highlighting the selected one
+        for (index, game) in state.games.iter().enumerate() {
+            let position = Vec2::xy(4, index as i32 + 3);
+            if index == state.selected {
+                pencil.set_foreground(Color::Yellow);
+                pencil.draw_text(&format!("> {}", game.name), position - Vec2::x(2));
+            } else {
+                pencil.set_foreground(Color::White);
+                pencil.draw_text(game.name, position);
+            }
+        }
+
+        // Draw the controls
+        pencil.set_foreground(Color::DarkGrey);
+        pencil.draw_text(HELP, Vec2::xy(2, menu_size.y - 2));
+
+        // Draw the high scores on top of the menu
+        if let Some(high_scores) = &state.high_scores {
+            pencil.set_origin(Vec2::zero());
+            highscores::draw_table(&mut pencil, &[], high_scores, None, "enter: continue", win_size / 2);
+        }
+    });
+
+    state.picked
+}
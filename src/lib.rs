@@ -0,0 +1,14 @@
+pub mod bindings;
+pub mod clock;
+pub mod collision;
+pub mod game;
+pub mod games;
+pub mod headless;
+pub mod highscores;
+pub mod keys;
+pub mod launcher;
+pub mod options;
+pub mod paths;
+pub mod pause;
+pub mod physics;
+pub mod replay;
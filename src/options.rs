@@ -0,0 +1,100 @@
+use std::fmt;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "usage: rusticade [--seed <number>] [--record <file>] [--replay <file>] \
+                          [--level <file>] [--edit-level <file>]\n\
+                         --record keeps the last game played only: every new game overwrites <file>";
+
+/*
+    Options defines what can be chosen on the command line.
+    - the seed of the random number generator of every game (random if None)
+    - the file to save a replay of the last game played to
+    - the replay to watch instead of opening the launcher
+    - the Breakout level to play instead of the built-in ones
+    - the Breakout level to open in the editor instead of the launcher
+*/
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub level: Option<PathBuf>,
+    pub edit_level: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum OptionsError {
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownArgument(String),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionsError::MissingValue(arg) => write!(f, "{} needs a value", arg),
+            OptionsError::InvalidValue(arg, value) => write!(f, "invalid value for {}: {}", arg, value),
+            OptionsError::UnknownArgument(arg) => write!(f, "unknown argument: {}", arg),
+        }
+    }
+}
+
+impl Options {
+    // Parses the arguments given to the program (without the program name).
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, OptionsError> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| OptionsError::MissingValue(arg.clone()));
+            match arg.as_str() {
+                "--seed" => {
+                    let value = value()?;
+                    let seed = value.parse().map_err(|_| OptionsError::InvalidValue(arg, value))?;
+                    options.seed = Some(seed);
+                }
+                "--record" => options.record = Some(PathBuf::from(value()?)),
+                "--replay" => options.replay = Some(PathBuf::from(value()?)),
+                "--level" => options.level = Some(PathBuf::from(value()?)),
+                "--edit-level" => options.edit_level = Some(PathBuf::from(value()?)),
+                _ => return Err(OptionsError::UnknownArgument(arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    // The seed for a new game: the one asked for, or a random one.
+    pub fn game_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, OptionsError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn seed() {
+        assert_eq!(parse(&[]).unwrap().seed, None);
+        assert_eq!(parse(&["--seed", "42"]).unwrap().seed, Some(42));
+        assert_eq!(parse(&["--seed", "42"]).unwrap().game_seed(), 42);
+    }
+
+    #[test]
+    fn files() {
+        let options = parse(&["--record", "last.replay", "--level", "levels/mine.txt"]).unwrap();
+        assert_eq!(options.record, Some(PathBuf::from("last.replay")));
+        assert_eq!(options.level, Some(PathBuf::from("levels/mine.txt")));
+        assert_eq!(options.replay, None);
+        assert!(matches!(parse(&["--level"]), Err(OptionsError::MissingValue(_))));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(parse(&["--seed"]), Err(OptionsError::MissingValue(_))));
+        assert!(matches!(parse(&["--seed", "x"]), Err(OptionsError::InvalidValue(_, _))));
+        assert!(matches!(parse(&["--speed"]), Err(OptionsError::UnknownArgument(_))));
+    }
+}
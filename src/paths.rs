@@ -0,0 +1,32 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+// Reads a directory from the environment variable `var`, falling back to
+// `fallback` inside the home directory, as the XDG base directory spec says.
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)),
+    }
+}
+
+// Where rusticade keeps the data it saves (like high scores).
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("rusticade"))
+}
+
+// Where rusticade reads the choices of the player from (like key bindings).
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("rusticade"))
+}
+
+// The error for a file whose contents do not make sense.
+pub fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// The name of a game as used in file names and config tables.
+pub fn slug(game: &str) -> String {
+    game.to_lowercase().replace(' ', "-")
+}
@@ -0,0 +1,293 @@
+use ruscii::drawing::Pencil;
+use ruscii::keyboard::{Key, KeyEvent};
+use ruscii::spatial::Vec2;
+
+use crate::bindings::{Action, Bindings};
+use crate::highscores::draw_panel;
+use crate::keys::key_name;
+
+const ITEMS: [&str; 5] = ["Resume", "Restart", "Controls", "Settings", "Quit to launcher"];
+const SETTINGS: [&str; 2] = ["show FPS", "show seed"];
+
+/*
+    Settings defines how a game is shown while it is played.
+    - whether the frame rate is shown in the top left corner
+    - whether the seed is shown in the bottom left corner
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub show_fps: bool,
+    pub show_seed: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            show_fps: true,
+            show_seed: true,
+        }
+    }
+}
+
+impl Settings {
+    fn toggle(&mut self, index: usize) {
+        match index {
+            0 => self.show_fps = !self.show_fps,
+            1 => self.show_seed = !self.show_seed,
+            _ => (),
+        }
+    }
+
+    fn value(&self, index: usize) -> bool {
+        match index {
+            0 => self.show_fps,
+            _ => self.show_seed,
+        }
+    }
+}
+
+// What the player chose to do from the pause menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Quit,
+}
+
+// The screen of the pause menu being looked at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Screen {
+    Menu,
+    Controls,
+    Settings,
+}
+
+/*
+    PauseMenu defines the state of the menu shown on top of a paused game.
+    - the screen being looked at
+    - the highlighted entry of the menu
+    - the highlighted action of the controls
+    - whether the next key pressed is bound to that action
+    - the highlighted entry of the settings
+*/
+pub struct PauseMenu {
+    screen: Screen,
+    selected: usize,
+    selected_action: usize,
+    rebinding: bool,
+    selected_setting: usize,
+}
+
+fn save_bindings(bindings: &Bindings) {
+    if let Err(error) = bindings.save() {
+        log::warn!("could not save the key bindings of {}: {}", bindings.game, error);
+    }
+}
+
+// Moves `selected` by `direction` among `len` entries, wrapping around.
+fn move_selection(selected: usize, direction: i32, len: usize) -> usize {
+    (selected as i32 + direction).rem_euclid(len as i32) as usize
+}
+
+impl PauseMenu {
+    pub fn new() -> PauseMenu {
+        PauseMenu {
+            screen: Screen::Menu,
+            selected: 0,
+            selected_action: 0,
+            rebinding: false,
+            selected_setting: 0,
+        }
+    }
+
+    // Handles a key pressed while the game is paused. Returns what the player
+    // chose to do, if anything. Bindings changed from the controls screen
+    // are saved right away.
+    pub fn handle_key_event(
+        &mut self,
+        key_event: KeyEvent,
+        settings: &mut Settings,
+        bindings: &mut Bindings,
+    ) -> Option<PauseAction> {
+        let key = match key_event {
+            KeyEvent::Pressed(key) => key,
+            _ => return None,
+        };
+
+        match self.screen {
+            Screen::Menu if bindings.action(key) == Some(Action::Pause) => return Some(PauseAction::Resume),
+            Screen::Menu => match key {
+                Key::Up | Key::W | Key::K => self.selected = move_selection(self.selected, -1, ITEMS.len()),
+                Key::Down | Key::S | Key::J => self.selected = move_selection(self.selected, 1, ITEMS.len()),
+                Key::Enter | Key::Space => match self.selected {
+                    0 => return Some(PauseAction::Resume),
+                    1 => return Some(PauseAction::Restart),
+                    2 => self.screen = Screen::Controls,
+                    3 => self.screen = Screen::Settings,
+                    _ => return Some(PauseAction::Quit),
+                },
+                _ => (),
+            },
+            Screen::Controls if self.rebinding => {
+                self.rebinding = false;
+                if key != Key::Esc {
+                    let action = bindings.entries()[self.selected_action].0;
+                    if bindings.set(action, vec![key]) {
+                        save_bindings(bindings);
+                    }
+                }
+            }
+            Screen::Controls => match key {
+                Key::Esc | Key::P => self.screen = Screen::Menu,
+                Key::Up | Key::W | Key::K => {
+                    self.selected_action = move_selection(self.selected_action, -1, bindings.entries().len())
+                }
+                Key::Down | Key::S | Key::J => {
+                    self.selected_action = move_selection(self.selected_action, 1, bindings.entries().len())
+                }
+                Key::Enter | Key::Space => self.rebinding = true,
+                Key::Backspace => {
+                    let action = bindings.entries()[self.selected_action].0;
+                    bindings.reset(action);
+                    save_bindings(bindings);
+                }
+                _ => (),
+            },
+            Screen::Settings => match key {
+                Key::Esc | Key::Backspace | Key::P => self.screen = Screen::Menu,
+                Key::Up | Key::W | Key::K => {
+                    self.selected_setting = move_selection(self.selected_setting, -1, SETTINGS.len())
+                }
+                Key::Down | Key::S | Key::J => {
+                    self.selected_setting = move_selection(self.selected_setting, 1, SETTINGS.len())
+                }
+                Key::Enter | Key::Space | Key::Left | Key::Right => settings.toggle(self.selected_setting),
+                _ => (),
+            },
+        }
+        None
+    }
+
+    // Draws the menu centered on `center`.
+    pub fn draw(&self, pencil: &mut Pencil, settings: &Settings, bindings: &Bindings, center: Vec2) {
+        let mut lines = Vec::new();
+        let highlight = match self.screen {
+            Screen::Menu => {
+                lines.push("PAUSED".to_string());
+                lines.push(String::new());
+                for (index, item) in ITEMS.iter().enumerate() {
+                    let cursor = if index == self.selected { ">" } else { " " };
+                    lines.push(format!("{} {}", cursor, item));
+                }
+                lines.push(String::new());
+                lines.push("enter: select  -  esc/p: resume".to_string());
+                Some(self.selected + 2)
+            }
+            Screen::Controls => {
+                lines.push("CONTROLS".to_string());
+                lines.push(String::new());
+                for (index, (action, keys)) in bindings.entries().iter().enumerate() {
+                    let selected = index == self.selected_action;
+                    let cursor = if selected { ">" } else { " " };
+                    let keys = if selected && self.rebinding {
+                        "press a key...".to_string()
+                    } else {
+                        keys.iter().filter_map(|key| key_name(*key)).collect::<Vec<_>>().join("/")
+                    };
+                    lines.push(format!("{} {:<15}{}", cursor, action.description(), keys));
+                }
+                lines.push(String::new());
+                lines.push("enter: rebind - bksp: reset - esc: back".to_string());
+                Some(self.selected_action + 2)
+            }
+            Screen::Settings => {
+                lines.push("SETTINGS".to_string());
+                lines.push(String::new());
+                for (index, name) in SETTINGS.iter().enumerate() {
+                    let cursor = if index == self.selected_setting { ">" } else { " " };
+                    let value = if settings.value(index) { "on" } else { "off" };
+                    lines.push(format!("{} {:<14}{}", cursor, name, value));
+                }
+                lines.push(String::new());
+                lines.push("enter: change  -  esc: back".to_string());
+                Some(self.selected_setting + 2)
+            }
+        };
+        draw_panel(pencil, &lines, highlight, center);
+    }
+}
+
+impl Default for PauseMenu {
+    fn default() -> PauseMenu {
+        PauseMenu::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings() -> Bindings {
+        Bindings::new("Test", &[(Action::Fire, &[Key::Space])])
+    }
+
+    // Presses each key in turn, returning what the last one chose to do.
+    fn press(
+        menu: &mut PauseMenu,
+        settings: &mut Settings,
+        bindings: &mut Bindings,
+        keys: &[Key],
+    ) -> Option<PauseAction> {
+        let mut action = None;
+        for key in keys {
+            action = menu.handle_key_event(KeyEvent::Pressed(*key), settings, bindings);
+        }
+        action
+    }
+
+    #[test]
+    fn menu_items() {
+        let (mut settings, mut bindings) = (Settings::default(), bindings());
+        let mut choose = |keys: &[Key]| press(&mut PauseMenu::new(), &mut settings, &mut bindings, keys);
+        assert_eq!(choose(&[Key::Enter]), Some(PauseAction::Resume));
+        assert_eq!(choose(&[Key::P]), Some(PauseAction::Resume));
+        assert_eq!(choose(&[Key::Down, Key::Enter]), Some(PauseAction::Restart));
+        // Going up from the first item wraps around to the last one
+        assert_eq!(choose(&[Key::Up, Key::Enter]), Some(PauseAction::Quit));
+        assert_eq!(choose(&[Key::A]), None);
+    }
+
+    #[test]
+    fn the_pause_keys_resume_the_game() {
+        let (mut settings, mut bindings) = (Settings::default(), bindings());
+        assert!(bindings.set(Action::Pause, vec![Key::Q]));
+        let mut menu = PauseMenu::new();
+        assert_eq!(press(&mut menu, &mut settings, &mut bindings, &[Key::P]), None);
+        assert_eq!(press(&mut menu, &mut settings, &mut bindings, &[Key::Q]), Some(PauseAction::Resume));
+    }
+
+    #[test]
+    fn settings_are_toggled_and_left_with_esc() {
+        let (mut settings, mut bindings) = (Settings::default(), bindings());
+        let mut menu = PauseMenu::new();
+        let keys = [Key::Down, Key::Down, Key::Down, Key::Enter];
+        assert_eq!(press(&mut menu, &mut settings, &mut bindings, &keys), None);
+        assert_eq!(menu.screen, Screen::Settings);
+
+        press(&mut menu, &mut settings, &mut bindings, &[Key::Enter, Key::Down, Key::Space]);
+        assert!(!settings.show_fps);
+        assert!(!settings.show_seed);
+
+        // Esc goes back to the menu instead of resuming the game
+        assert_eq!(press(&mut menu, &mut settings, &mut bindings, &[Key::Esc]), None);
+        assert_eq!(menu.screen, Screen::Menu);
+        assert_eq!(press(&mut menu, &mut settings, &mut bindings, &[Key::Esc]), Some(PauseAction::Resume));
+    }
+
+    #[test]
+    fn key_releases_are_ignored() {
+        let mut menu = PauseMenu::new();
+        let action = menu.handle_key_event(KeyEvent::Released(Key::Enter), &mut Settings::default(), &mut bindings());
+        assert_eq!(action, None);
+    }
+}
@@ -0,0 +1,122 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use ruscii::spatial::Vec2;
+
+/*
+    Vec2f is a position or a velocity in cell units that can be anywhere
+    between cells. It is rounded to the nearest cell to be drawn.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2f {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2f {
+    pub const fn xy(x: f32, y: f32) -> Vec2f {
+        Vec2f { x, y }
+    }
+
+    pub const fn zero() -> Vec2f {
+        Vec2f { x: 0.0, y: 0.0 }
+    }
+
+    // A vector of the given length, `angle` radians clockwise from the
+    // positive x axis (y grows downwards on screen).
+    pub fn from_angle(angle: f32, length: f32) -> Vec2f {
+        Vec2f::xy(angle.cos() * length, angle.sin() * length)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    // The same vector scaled to the given length.
+    pub fn with_length(&self, length: f32) -> Vec2f {
+        let current = self.length();
+        if current == 0.0 {
+            return *self;
+        }
+        *self * (length / current)
+    }
+
+    // The cell this position is drawn in.
+    pub fn to_cell(&self) -> Vec2 {
+        Vec2::xy(self.x.round() as i32, self.y.round() as i32)
+    }
+}
+
+impl From<Vec2> for Vec2f {
+    fn from(cell: Vec2) -> Vec2f {
+        Vec2f::xy(cell.x as f32, cell.y as f32)
+    }
+}
+
+impl Add for Vec2f {
+    type Output = Vec2f;
+
+    fn add(self, other: Vec2f) -> Vec2f {
+        Vec2f::xy(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2f {
+    fn add_assign(&mut self, other: Vec2f) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec2f {
+    type Output = Vec2f;
+
+    fn sub(self, other: Vec2f) -> Vec2f {
+        Vec2f::xy(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vec2f {
+    fn sub_assign(&mut self, other: Vec2f) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f32> for Vec2f {
+    type Output = Vec2f;
+
+    fn mul(self, factor: f32) -> Vec2f {
+        Vec2f::xy(self.x * factor, self.y * factor)
+    }
+}
+
+impl Neg for Vec2f {
+    type Output = Vec2f;
+
+    fn neg(self) -> Vec2f {
+        Vec2f::xy(-self.x, -self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_cell_rounds_to_the_nearest_cell() {
+        assert_eq!(Vec2f::xy(2.4, 2.6).to_cell(), Vec2::xy(2, 3));
+        assert_eq!(Vec2f::xy(-0.4, -0.6).to_cell(), Vec2::xy(0, -1));
+    }
+
+    #[test]
+    fn with_length_keeps_the_direction() {
+        let scaled = Vec2f::xy(3.0, -4.0).with_length(10.0);
+        assert_eq!(scaled, Vec2f::xy(6.0, -8.0));
+        assert_eq!(Vec2f::zero().with_length(10.0), Vec2f::zero());
+    }
+
+    #[test]
+    fn from_angle_points_down_for_a_quarter_turn() {
+        let down = Vec2f::from_angle(std::f32::consts::FRAC_PI_2, 2.0);
+        assert!(down.x.abs() < 1e-6);
+        assert!((down.y - 2.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ruscii::spatial::Vec2;
+
+use crate::bindings::{Action, ActionEvent};
+use crate::game::Input;
+use crate::paths::invalid_data;
+
+const HEADER: &str = "rusticade-replay 2";
+
+/*
+    Replay holds everything needed to play a game again exactly.
+    - the name of the game
+    - the seed of its RNG and the window size it was created with
+    - the level it was played on, if it was given one (see `--level`)
+    - the input of every step, in order
+
+    On disk it is a text file with a few header lines (with `level N` and
+    the N lines of the level after them if there is one) followed by one line
+    per run of identical steps: the number of steps, then `+action` for every
+    action started, `-action` for every action stopped and `action` for every
+    action held, and `=WxH` if the window was resized to W columns and H rows.
+    Actions are saved rather than keys so that changing the key bindings does
+    not change how a replay plays.
+*/
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub game: String,
+    pub seed: u64,
+    pub win_size: Vec2,
+    pub level: Option<String>,
+    pub steps: Vec<Input>,
+}
+
+fn format_input(input: &Input) -> String {
+    let mut tokens = Vec::new();
+    for event in &input.events {
+        tokens.push(match event {
+            ActionEvent::Pressed(action) => format!("+{}", action.name()),
+            ActionEvent::Released(action) => format!("-{}", action.name()),
+        });
+    }
+    for action in &input.actions_down {
+        tokens.push(action.name().to_string());
+    }
+    if let Some(win_size) = input.win_size {
+        tokens.push(format!("={}x{}", win_size.x, win_size.y));
+    }
+    tokens.join(" ")
+}
+
+fn parse_action(name: &str) -> io::Result<Action> {
+    Action::from_name(name).ok_or_else(|| invalid_data(format!("unknown action: {}", name)))
+}
+
+fn parse_size(text: &str, separator: char) -> io::Result<Vec2> {
+    match text.split_once(separator).map(|(x, y)| (x.parse::<i32>(), y.parse::<i32>())) {
+        Some((Ok(x), Ok(y))) => Ok(Vec2::xy(x, y)),
+        _ => Err(invalid_data(format!("invalid size: {}", text))),
+    }
+}
+
+fn parse_input<'a>(tokens: impl Iterator<Item = &'a str>) -> io::Result<Input> {
+    let mut input = Input::default();
+    for token in tokens {
+        if let Some(name) = token.strip_prefix('+') {
+            input.events.push(ActionEvent::Pressed(parse_action(name)?));
+        } else if let Some(name) = token.strip_prefix('-') {
+            input.events.push(ActionEvent::Released(parse_action(name)?));
+        } else if let Some(size) = token.strip_prefix('=') {
+            input.win_size = Some(parse_size(size, 'x')?);
+        } else {
+            input.actions_down.push(parse_action(token)?);
+        }
+    }
+    Ok(input)
+}
+
+impl Replay {
+    pub fn new(game: &str, seed: u64, win_size: Vec2) -> Replay {
+        Replay {
+            game: game.to_string(),
+            seed,
+            win_size,
+            level: None,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Replay::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Replay> {
+        let mut lines = text.lines().peekable();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid_data("not a rusticade replay".to_string()));
+        }
+
+        let mut field = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| invalid_data(format!("missing {}", name.trim())))
+        };
+        let game = field("game ")?;
+        let seed = field("seed ")?;
+        let size = field("size ")?;
+
+        let seed = seed.parse().map_err(|_| invalid_data(format!("invalid seed: {}", seed)))?;
+        let win_size = parse_size(&size, ' ')?;
+
+        let mut replay = Replay::new(&game, seed, win_size);
+        if let Some(count) = lines.peek().copied().and_then(|line| line.strip_prefix("level ")) {
+            let count: usize =
+                count.trim().parse().map_err(|_| invalid_data(format!("invalid level: {}", count)))?;
+            lines.next();
+            let level: Vec<&str> = lines.by_ref().take(count).collect();
+            replay.level = Some(level.join("\n"));
+        }
+        for line in lines {
+            let mut tokens = line.split_whitespace();
+            let count: usize = match tokens.next() {
+                Some(count) => count.parse().map_err(|_| invalid_data(format!("invalid line: {}", line)))?,
+                None => continue,
+            };
+            let input = parse_input(tokens)?;
+            replay.steps.extend(std::iter::repeat_n(input, count));
+        }
+        Ok(replay)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\ngame {}\nseed {}\nsize {} {}\n",
+            HEADER, self.game, self.seed, self.win_size.x, self.win_size.y
+        );
+        if let Some(level) = &self.level {
+            let level = level.trim_end_matches('\n');
+            text.push_str(&format!("level {}\n{}\n", level.lines().count(), level));
+        }
+
+        // Write runs of identical steps on a single line
+        let mut index = 0;
+        while index < self.steps.len() {
+            let input = &self.steps[index];
+            let count = self.steps[index..].iter().take_while(|step| *step == input).count();
+            let tokens = format_input(input);
+            if tokens.is_empty() {
+                text.push_str(&format!("{}\n", count));
+            } else {
+                text.push_str(&format!("{} {}\n", count, tokens));
+            }
+            index += count;
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(events: Vec<ActionEvent>, actions_down: Vec<Action>, win_size: Option<Vec2>) -> Input {
+        Input { events, actions_down, win_size }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut replay = Replay::new("Pong", 1234, Vec2::xy(80, 24));
+        replay.steps = vec![
+            Input::default(),
+            Input::default(),
+            input(vec![ActionEvent::Pressed(Action::Fire)], vec![Action::Fire], None),
+            input(vec![], vec![Action::MoveUp, Action::Player2Down], None),
+            input(vec![ActionEvent::Released(Action::Fire)], vec![], Some(Vec2::xy(100, 30))),
+            Input::default(),
+        ];
+        let text = replay.to_text();
+        assert!(text.contains("\n2\n"));
+
+        let parsed = Replay::parse(&text).unwrap();
+        assert_eq!(parsed.game, "Pong");
+        assert_eq!(parsed.seed, 1234);
+        assert_eq!(parsed.win_size, Vec2::xy(80, 24));
+        assert_eq!(parsed.level, None);
+        assert_eq!(parsed.steps, replay.steps);
+    }
+
+    #[test]
+    fn level_round_trip() {
+        let mut replay = Replay::new("Breakout", 1, Vec2::xy(80, 24));
+        replay.level = Some("HHHH\n=X==\n".to_string());
+        replay.steps = vec![input(vec![], vec![Action::MoveLeft], None); 3];
+
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(parsed.level.as_deref(), Some("HHHH\n=X=="));
+        assert_eq!(parsed.steps, replay.steps);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let header = format!("{}\ngame Pong\nseed 1\nsize 80 24\n", HEADER);
+        assert!(Replay::parse("rusticade-replay 1\n").is_err());
+        assert!(Replay::parse(&format!("{}\ngame Pong\n", HEADER)).is_err());
+        assert!(Replay::parse(&header.replace("seed 1", "seed x")).is_err());
+        assert!(Replay::parse(&format!("{}3 +jump\n", header)).is_err());
+        assert!(Replay::parse(&format!("{}x fire\n", header)).is_err());
+        assert!(Replay::parse(&format!("{}1 =80\n", header)).is_err());
+        assert_eq!(Replay::parse(&format!("{}3 fire\n", header)).unwrap().steps.len(), 3);
+    }
+}